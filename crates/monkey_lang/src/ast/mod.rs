@@ -0,0 +1,662 @@
+//! The syntax tree the parser produces and the evaluator runs.
+//!
+//! The enums are `#[non_exhaustive]` so new kinds of statements, expressions,
+//! patterns and parameters can be added without a breaking change, code
+//! matching on them needs a wildcard arm. The variants that exist and their
+//! fields are stable and can be built directly, along with the constructors
+//! of the structs, to rewrite a program before running it. Spans of nodes
+//! built by hand can be left as `Span::default()`, they are only used to
+//! report where errors happened
+
+pub mod visit;
+
+use crate::token::Span;
+use num_bigint::BigInt;
+use std::fmt::{Debug, Display, Formatter};
+
+/// Enum representing the different type of statements we handle
+#[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
+pub enum Statement {
+    /// Represents let statements of the form
+    /// let <pattern> = <expression>;
+    /// e.g let a = 2; or let [first, ...rest] = list;
+    Let { target: Pattern, value: Expression },
+    /// Represents statements of the form
+    /// return <expression>;
+    /// e.g return 2 + 2;
+    Return { return_value: Expression },
+    /// Represents loops of the form
+    /// while (<expression>) <block>
+    /// the body runs until the condition is falsy, the loop itself evaluates to null
+    While { condition: Expression, body: Block },
+    /// Represents loops of the form
+    /// for (<target> in <expression>) <block>
+    /// iterates array elements, string characters or [key, value] pairs of a hash
+    For {
+        target: Pattern,
+        iterable: Expression,
+        body: Block,
+    },
+    /// Exits the innermost enclosing loop
+    Break,
+    /// Skips to the next condition check of the innermost enclosing loop
+    Continue,
+    /// Represents statements of the form
+    /// throw <expression>;
+    /// raises a runtime error holding the value, e.g. throw "not found"; or throw {"code": 1};
+    Throw { value: Expression, span: Span },
+    /// Represents statements of the form
+    /// import "<path>" as <identifier>;
+    /// binds a hash of the module's exports, the path is relative to the importing file.
+    /// Only valid at the top level of a program
+    Import { path: String, alias: String },
+    /// Represents statements of the form
+    /// export let <pattern> = <expression>;
+    /// always wraps a let statement, only valid at the top level of a program
+    Export(Box<Statement>),
+    /// Wrapper for an expression
+    Expression(Expression),
+}
+
+impl Display for Statement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Statement::Let { target, value } => {
+                let statement = format!("let {target} = {value};");
+                f.write_str(&statement)
+            }
+            Statement::Return { return_value } => f.write_str(&format!("return {return_value};")),
+            Statement::While { condition, body } => {
+                f.write_str(&format!("while({condition}){body}"))
+            }
+            Statement::For {
+                target,
+                iterable,
+                body,
+            } => f.write_str(&format!("for({target} in {iterable}){body}")),
+            Statement::Break => f.write_str("break;"),
+            Statement::Continue => f.write_str("continue;"),
+            Statement::Throw { value, .. } => f.write_str(&format!("throw {value};")),
+            Statement::Import { path, alias } => {
+                f.write_str(&format!("import {} as {alias};", quote_string(path)))
+            }
+            Statement::Export(statement) => f.write_str(&format!("export {statement}")),
+            Statement::Expression(expression) => f.write_str(&format!("{expression};")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Block {
+    pub(crate) statements: Vec<Statement>,
+}
+
+impl Block {
+    pub fn new(statements: Vec<Statement>) -> Self {
+        Self { statements }
+    }
+
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+
+    pub fn statements_mut(&mut self) -> &mut Vec<Statement> {
+        &mut self.statements
+    }
+}
+
+impl Display for Block {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let block_strings = self
+            .statements
+            .iter()
+            .map(|statement| statement.to_string())
+            .collect::<Vec<String>>();
+        let block_string = block_strings.join("\n");
+        f.write_str(&format!("{{{}}}", block_string.as_str()))
+    }
+}
+
+/// Enum representing the different type of expressions we handle
+#[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
+pub enum Expression {
+    /// Represents the name of something
+    Identifier(String, Span),
+    /// Represents an integer
+    IntegerLiteral(i64),
+    /// Represents an integer too big for an i64
+    BigIntegerLiteral(BigInt),
+    /// Represents a double quoted string
+    StringLiteral(String),
+    /// Represents a string with embedded expressions e.g. "hello ${name}", it
+    /// evaluates to the parts joined together, with the values of the
+    /// expressions converted like str does
+    InterpolatedString(Vec<StringPart>),
+    /// Represents an array e.g. [1, 2 + 3]
+    ArrayLiteral(Vec<Expression>),
+    /// Represents a hash as key value pairs e.g. {"a": 1, 2: true}
+    HashLiteral(Vec<(Expression, Expression)>),
+    /// Represents an index into an array or hash e.g. a[0]
+    Index {
+        left: Box<Expression>,
+        index: Box<Expression>,
+        span: Span,
+    },
+    /// Holds a prefix expression of the form
+    /// <prefix><expression>
+    /// e.g. -10 where - is the operator and 10 is the right expression
+    Prefix {
+        operator: String,
+        right: Box<Expression>,
+        span: Span,
+    },
+    /// Hods an infix expression of the form
+    /// <expression><operator><expression>
+    Infix {
+        left: Box<Expression>,
+        operator: String,
+        right: Box<Expression>,
+        span: Span,
+    },
+    /// Represents an assignment to an existing binding or an index into one
+    /// <target><operator><expression> where operator is =, +=, -=, *= or /=
+    /// e.g. x += 1 or a[0] = 2
+    Assign {
+        target: Box<Expression>,
+        operator: String,
+        value: Box<Expression>,
+        span: Span,
+    },
+    /// Represents a boolean value i.e true or false
+    Boolean(bool),
+    /// Represents an If block, with any number of else if branches and an optional else
+    /// e.g. if (a) { 1 } else if (b) { 2 } else { 3 }
+    If {
+        condition: Box<Expression>,
+        consequence: Block,
+        else_ifs: Vec<(Expression, Block)>,
+        alternative: Option<Block>,
+    },
+    /// Represents a match on a value, the first arm whose pattern matches is run
+    /// e.g. match (x) { 1 => "one", [a, b] => a + b, _ => 0 }
+    Match {
+        value: Box<Expression>,
+        arms: Vec<MatchArm>,
+        span: Span,
+    },
+    /// Runs the body, if it raises a runtime error the catch block runs with
+    /// the error bound to the name, e.g. try { risky() } catch (e) { e["message"] }
+    Try {
+        body: Block,
+        name: String,
+        catch: Block,
+    },
+    /// Represents a function definition
+    FunctionLiteral {
+        parameters: Vec<Parameter>,
+        body: Block,
+    },
+    /// Represents a function call, named arguments come after positional ones
+    /// e.g. request(url, retries: 3)
+    FunctionCall {
+        function: Box<Expression>,
+        #[allow(clippy::vec_box)]
+        arguments: Vec<Box<Expression>>,
+        named_arguments: Vec<(String, Expression)>,
+        span: Span,
+    },
+}
+
+impl Expression {
+    /// Where the expression was parsed from, only kept for expressions
+    /// that can fail when evaluated
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Expression::Identifier(_, span)
+            | Expression::Index { span, .. }
+            | Expression::Prefix { span, .. }
+            | Expression::Infix { span, .. }
+            | Expression::Assign { span, .. }
+            | Expression::Match { span, .. }
+            | Expression::FunctionCall { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+
+    /// Identifiers and indexes into identifiers can be assigned to
+    pub(crate) fn is_assignable(&self) -> bool {
+        match self {
+            Expression::Identifier(..) => true,
+            Expression::Index { left, .. } => left.is_assignable(),
+            _ => false,
+        }
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expression::Identifier(value, _) => f.write_str(value.as_str()),
+            Expression::IntegerLiteral(value) => f.write_str(&format!("{}", value)),
+            Expression::BigIntegerLiteral(value) => f.write_str(&format!("{}", value)),
+            Expression::StringLiteral(value) => f.write_str(&quote_string(value)),
+            Expression::InterpolatedString(parts) => {
+                let parts = parts
+                    .iter()
+                    .map(|part| match part {
+                        StringPart::Text(text) => escape_string(text),
+                        StringPart::Expression(expression) => format!("${{{expression}}}"),
+                    })
+                    .collect::<String>();
+                f.write_str(&format!("\"{parts}\""))
+            }
+            Expression::ArrayLiteral(elements) => {
+                let comma_seperated_elements = elements
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                f.write_str(&format!("[{comma_seperated_elements}]"))
+            }
+            Expression::HashLiteral(pairs) => {
+                let comma_seperated_pairs = pairs
+                    .iter()
+                    .map(|(key, value)| format!("{key}: {value}"))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                f.write_str(&format!("{{{comma_seperated_pairs}}}"))
+            }
+            Expression::Index { left, index, .. } => f.write_str(&format!("({left}[{index}])")),
+            Expression::Prefix {
+                operator, right, ..
+            } => f.write_str(&format!("({operator}{right})")),
+            Expression::Infix {
+                left,
+                operator,
+                right,
+                ..
+            } => f.write_str(&format!("({left} {operator} {right})")),
+            Expression::Assign {
+                target,
+                operator,
+                value,
+                ..
+            } => f.write_str(&format!("({target} {operator} {value})")),
+            Expression::Boolean(value) => f.write_str(&format!("{}", value)),
+            Expression::If {
+                condition,
+                consequence,
+                else_ifs,
+                alternative,
+            } => {
+                let mut if_expression_string = format!("if({condition}){consequence}");
+                for (condition, consequence) in else_ifs {
+                    if_expression_string.push_str(&format!(" else if({condition}){consequence}"));
+                }
+                if let Some(alternative) = alternative {
+                    if_expression_string.push_str(&format!(" else{alternative}"));
+                }
+                f.write_str(if_expression_string.as_str())
+            }
+            Expression::Match { value, arms, .. } => {
+                let comma_seperated_arms = arms
+                    .iter()
+                    .map(|arm| arm.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                f.write_str(&format!("match({value}){{{comma_seperated_arms}}}"))
+            }
+            Expression::Try { body, name, catch } => {
+                f.write_str(&format!("try{body} catch({name}){catch}"))
+            }
+            Expression::FunctionLiteral { parameters, body } => {
+                let comma_seperated_parameters = join_parameters(parameters);
+                f.write_str(&format!("fn({comma_seperated_parameters}){body}"))
+            }
+            Expression::FunctionCall {
+                function,
+                arguments,
+                named_arguments,
+                ..
+            } => {
+                let comma_seperated_arguments = arguments
+                    .iter()
+                    .map(|arg| arg.to_string())
+                    .chain(
+                        named_arguments
+                            .iter()
+                            .map(|(name, arg)| format!("{name}: {arg}")),
+                    )
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                f.write_str(&format!("{function}({comma_seperated_arguments})"))
+            }
+        }
+    }
+}
+
+/// A piece of an interpolated string
+#[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
+pub enum StringPart {
+    Text(String),
+    Expression(Expression),
+}
+
+/// A parameter in a function definition
+#[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
+pub enum Parameter {
+    /// Has to be passed by the caller e.g. fn(x)
+    Required(String),
+    /// Evaluated when the caller doesn't pass the parameter, it can refer to the
+    /// parameters before it e.g. fn(x, y = x * 2)
+    Default(String, Expression),
+    /// Collects the remaining positional arguments into an array, only valid as
+    /// the last parameter e.g. fn(first, ...rest)
+    Variadic(String),
+}
+
+impl Parameter {
+    pub fn name(&self) -> &str {
+        match self {
+            Parameter::Required(name) | Parameter::Default(name, _) | Parameter::Variadic(name) => {
+                name
+            }
+        }
+    }
+}
+
+impl Display for Parameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Parameter::Required(name) => f.write_str(name),
+            Parameter::Default(name, default) => f.write_str(&format!("{name} = {default}")),
+            Parameter::Variadic(name) => f.write_str(&format!("...{name}")),
+        }
+    }
+}
+
+pub(crate) fn join_parameters(parameters: &[Parameter]) -> String {
+    parameters
+        .iter()
+        .map(|parameter| parameter.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// A single arm of a match expression, of the form <pattern> => <body>
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchArm {
+    pub(crate) pattern: Pattern,
+    pub(crate) body: Block,
+}
+
+impl MatchArm {
+    pub fn new(pattern: Pattern, body: Block) -> Self {
+        Self { pattern, body }
+    }
+
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    pub fn body(&self) -> &Block {
+        &self.body
+    }
+}
+
+impl Display for MatchArm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{} => {}", self.pattern, self.body))
+    }
+}
+
+/// Describes the shape of a value, used by match expressions and as the
+/// target of let statements and for loops
+#[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
+pub enum Pattern {
+    /// Matches anything without binding it i.e. _
+    Wildcard,
+    /// Matches anything and binds it to the name
+    Identifier(String),
+    /// Matches values equal to the literal
+    Integer(i64),
+    /// An integer literal that doesn't fit in an i64
+    BigInteger(BigInt),
+    String(String),
+    Boolean(bool),
+    /// Matches arrays of the same length whose elements match each pattern
+    /// e.g. [a, 1, _]
+    Array(Vec<Pattern>),
+    /// Only valid as the last element of an array pattern, collects the
+    /// remaining elements into an array e.g. [first, ...rest]
+    Rest(String),
+    /// Matches hashes that contain each key, binding the value to a name
+    /// that is the same as the key e.g. {name, age}
+    Hash(Vec<String>),
+}
+
+impl Pattern {
+    /// Names bound when a value matches the pattern, in order
+    pub fn bound_names(&self) -> Vec<&str> {
+        match self {
+            Pattern::Identifier(name) | Pattern::Rest(name) => vec![name],
+            Pattern::Array(patterns) => patterns
+                .iter()
+                .flat_map(|pattern| pattern.bound_names())
+                .collect(),
+            Pattern::Hash(keys) => keys.iter().map(|key| key.as_str()).collect(),
+            Pattern::Wildcard
+            | Pattern::Integer(_)
+            | Pattern::BigInteger(_)
+            | Pattern::String(_)
+            | Pattern::Boolean(_) => Vec::new(),
+        }
+    }
+
+    /// Irrefutable patterns can only fail to match because of the value's shape,
+    /// these are the patterns allowed in let statements and for loops
+    pub fn is_irrefutable(&self) -> bool {
+        match self {
+            Pattern::Wildcard | Pattern::Identifier(_) | Pattern::Rest(_) | Pattern::Hash(_) => {
+                true
+            }
+            Pattern::Array(patterns) => patterns.iter().all(|pattern| pattern.is_irrefutable()),
+            Pattern::Integer(_)
+            | Pattern::BigInteger(_)
+            | Pattern::String(_)
+            | Pattern::Boolean(_) => false,
+        }
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Wildcard => f.write_str("_"),
+            Pattern::Identifier(name) => f.write_str(name),
+            Pattern::Integer(value) => f.write_str(&format!("{value}")),
+            Pattern::BigInteger(value) => f.write_str(&format!("{value}")),
+            Pattern::String(value) => f.write_str(&quote_string(value)),
+            Pattern::Boolean(value) => f.write_str(&format!("{value}")),
+            Pattern::Array(patterns) => {
+                let comma_seperated_patterns = patterns
+                    .iter()
+                    .map(|pattern| pattern.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                f.write_str(&format!("[{comma_seperated_patterns}]"))
+            }
+            Pattern::Rest(name) => f.write_str(&format!("...{name}")),
+            Pattern::Hash(keys) => f.write_str(&format!("{{{}}}", keys.join(", "))),
+        }
+    }
+}
+
+/// Wraps a string in double quotes, escaping it so the lexer reads back the same value
+pub(crate) fn quote_string(value: &str) -> String {
+    format!("\"{}\"", escape_string(value))
+}
+
+/// Escapes the value so it reads back as the same text inside a string literal
+fn escape_string(value: &str) -> String {
+    let mut escaped = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(char_value) = chars.next() {
+        match char_value {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            // only a $ before a { would start an interpolation
+            '$' if chars.peek() == Some(&'{') => escaped.push_str("\\$"),
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
+
+/// Represents the program as a series of statements
+#[derive(Debug, PartialEq, Clone)]
+pub struct Program {
+    pub(crate) statements: Vec<Statement>,
+}
+
+impl Program {
+    pub fn new(statements: Vec<Statement>) -> Self {
+        Self { statements }
+    }
+
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+
+    pub fn statements_mut(&mut self) -> &mut Vec<Statement> {
+        &mut self.statements
+    }
+}
+
+impl Display for Program {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let program_strings = self
+            .statements
+            .iter()
+            .map(|statement| statement.to_string())
+            .collect::<Vec<String>>();
+        let program_string = program_strings.join("\n");
+        f.write_str(program_string.as_str())
+    }
+}
+
+/// Resets every span to the default, so nodes built by hand in tests compare
+/// equal to parsed ones
+#[cfg(test)]
+struct ClearSpans;
+
+#[cfg(test)]
+impl visit::VisitorMut for ClearSpans {
+    fn visit_statement_mut(&mut self, statement: &mut Statement) {
+        if let Statement::Throw { span, .. } = statement {
+            *span = Span::default();
+        }
+        visit::walk_statement_mut(self, statement);
+    }
+
+    fn visit_expression_mut(&mut self, expression: &mut Expression) {
+        match expression {
+            Expression::Identifier(_, span)
+            | Expression::Index { span, .. }
+            | Expression::Prefix { span, .. }
+            | Expression::Infix { span, .. }
+            | Expression::Assign { span, .. }
+            | Expression::Match { span, .. }
+            | Expression::FunctionCall { span, .. } => *span = Span::default(),
+            _ => {}
+        }
+        visit::walk_expression_mut(self, expression);
+    }
+}
+
+#[cfg(test)]
+impl Program {
+    pub(crate) fn without_spans(mut self) -> Self {
+        visit::VisitorMut::visit_program_mut(&mut ClearSpans, &mut self);
+        self
+    }
+}
+
+#[cfg(test)]
+impl Block {
+    pub(crate) fn without_spans(mut self) -> Self {
+        visit::VisitorMut::visit_block_mut(&mut ClearSpans, &mut self);
+        self
+    }
+}
+
+#[cfg(test)]
+impl Expression {
+    pub(crate) fn without_spans(mut self) -> Self {
+        visit::VisitorMut::visit_expression_mut(&mut ClearSpans, &mut self);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{Expression, Parameter, Pattern, Program, Statement};
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::token::Span;
+
+    #[test]
+    fn ast_as_string() {
+        let program = Program::new(vec![
+            Statement::Let {
+                target: Pattern::Identifier("my_var".to_string()),
+                value: Expression::Identifier("another_var".to_string(), Span::default()),
+            },
+            Statement::Return {
+                return_value: Expression::Identifier("my_var".to_string(), Span::default()),
+            },
+        ]);
+        assert_eq!(
+            program.to_string(),
+            "let my_var = another_var;\n\
+            return my_var;"
+        );
+    }
+
+    #[test]
+    fn inspect_parsed_program() {
+        let program = Parser::new(Lexer::new(
+            "let f = fn(x, ...rest) { match (x) { [a, _] => a } };".chars(),
+        ))
+        .parse_program()
+        .unwrap();
+
+        let [Statement::Let { target, value }] = program.statements() else {
+            panic!("expected a single let statement");
+        };
+        assert_eq!(target.bound_names(), vec!["f"]);
+        let Expression::FunctionLiteral { parameters, body } = value else {
+            panic!("expected a function literal");
+        };
+        assert_eq!(
+            parameters.iter().map(Parameter::name).collect::<Vec<_>>(),
+            vec!["x", "rest"]
+        );
+
+        let [Statement::Expression(Expression::Match { arms, span, .. })] = body.statements()
+        else {
+            panic!("expected a match expression");
+        };
+        assert_eq!(span.start.column, 26);
+        assert_eq!(arms[0].pattern().to_string(), "[a, _]");
+        assert!(arms[0].pattern().is_irrefutable());
+        assert_eq!(arms[0].body().statements().len(), 1);
+    }
+}
@@ -0,0 +1,445 @@
+use crate::ast::{Block, Expression, MatchArm, Parameter, Pattern, Program, Statement, StringPart};
+
+/// Goes through the nodes of a program. Every method defaults to the walk
+/// function of the same node, which visits its children in source order, so an
+/// implementation only overrides the nodes it cares about and calls the walk
+/// function from them to keep going deeper
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program)
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement)
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression)
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block)
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        walk_pattern(self, pattern)
+    }
+
+    fn visit_parameter(&mut self, parameter: &Parameter) {
+        walk_parameter(self, parameter)
+    }
+
+    fn visit_match_arm(&mut self, arm: &MatchArm) {
+        walk_match_arm(self, arm)
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for statement in &program.statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Let { target, value } => {
+            visitor.visit_pattern(target);
+            visitor.visit_expression(value);
+        }
+        Statement::Return { return_value } => visitor.visit_expression(return_value),
+        Statement::While { condition, body } => {
+            visitor.visit_expression(condition);
+            visitor.visit_block(body);
+        }
+        Statement::For {
+            target,
+            iterable,
+            body,
+        } => {
+            visitor.visit_pattern(target);
+            visitor.visit_expression(iterable);
+            visitor.visit_block(body);
+        }
+        Statement::Throw { value, .. } => visitor.visit_expression(value),
+        Statement::Export(statement) => visitor.visit_statement(statement),
+        Statement::Expression(expression) => visitor.visit_expression(expression),
+        Statement::Break | Statement::Continue | Statement::Import { .. } => {}
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::InterpolatedString(parts) => {
+            for part in parts {
+                if let StringPart::Expression(expression) = part {
+                    visitor.visit_expression(expression);
+                }
+            }
+        }
+        Expression::ArrayLiteral(elements) => {
+            for element in elements {
+                visitor.visit_expression(element);
+            }
+        }
+        Expression::HashLiteral(pairs) => {
+            for (key, value) in pairs {
+                visitor.visit_expression(key);
+                visitor.visit_expression(value);
+            }
+        }
+        Expression::Index { left, index, .. } => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(index);
+        }
+        Expression::Prefix { right, .. } => visitor.visit_expression(right),
+        Expression::Infix { left, right, .. } => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        Expression::Assign { target, value, .. } => {
+            visitor.visit_expression(target);
+            visitor.visit_expression(value);
+        }
+        Expression::If {
+            condition,
+            consequence,
+            else_ifs,
+            alternative,
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_block(consequence);
+            for (condition, consequence) in else_ifs {
+                visitor.visit_expression(condition);
+                visitor.visit_block(consequence);
+            }
+            if let Some(alternative) = alternative {
+                visitor.visit_block(alternative);
+            }
+        }
+        Expression::Match { value, arms, .. } => {
+            visitor.visit_expression(value);
+            for arm in arms {
+                visitor.visit_match_arm(arm);
+            }
+        }
+        Expression::Try { body, catch, .. } => {
+            visitor.visit_block(body);
+            visitor.visit_block(catch);
+        }
+        Expression::FunctionLiteral { parameters, body } => {
+            for parameter in parameters {
+                visitor.visit_parameter(parameter);
+            }
+            visitor.visit_block(body);
+        }
+        Expression::FunctionCall {
+            function,
+            arguments,
+            named_arguments,
+            ..
+        } => {
+            visitor.visit_expression(function);
+            for argument in arguments {
+                visitor.visit_expression(argument);
+            }
+            for (_, argument) in named_arguments {
+                visitor.visit_expression(argument);
+            }
+        }
+        Expression::Identifier(..)
+        | Expression::IntegerLiteral(_)
+        | Expression::BigIntegerLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::Boolean(_) => {}
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &Block) {
+    for statement in &block.statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_pattern<V: Visitor + ?Sized>(visitor: &mut V, pattern: &Pattern) {
+    if let Pattern::Array(patterns) = pattern {
+        for pattern in patterns {
+            visitor.visit_pattern(pattern);
+        }
+    }
+}
+
+pub fn walk_parameter<V: Visitor + ?Sized>(visitor: &mut V, parameter: &Parameter) {
+    if let Parameter::Default(_, default) = parameter {
+        visitor.visit_expression(default);
+    }
+}
+
+pub fn walk_match_arm<V: Visitor + ?Sized>(visitor: &mut V, arm: &MatchArm) {
+    visitor.visit_pattern(&arm.pattern);
+    visitor.visit_block(&arm.body);
+}
+
+/// Like Visitor, but with mutable access to the nodes so they can be rewritten
+/// in place, e.g. by replacing `*expression` in visit_expression_mut
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program)
+    }
+
+    fn visit_statement_mut(&mut self, statement: &mut Statement) {
+        walk_statement_mut(self, statement)
+    }
+
+    fn visit_expression_mut(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression)
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        walk_block_mut(self, block)
+    }
+
+    fn visit_pattern_mut(&mut self, pattern: &mut Pattern) {
+        walk_pattern_mut(self, pattern)
+    }
+
+    fn visit_parameter_mut(&mut self, parameter: &mut Parameter) {
+        walk_parameter_mut(self, parameter)
+    }
+
+    fn visit_match_arm_mut(&mut self, arm: &mut MatchArm) {
+        walk_match_arm_mut(self, arm)
+    }
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    for statement in &mut program.statements {
+        visitor.visit_statement_mut(statement);
+    }
+}
+
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut Statement) {
+    match statement {
+        Statement::Let { target, value } => {
+            visitor.visit_pattern_mut(target);
+            visitor.visit_expression_mut(value);
+        }
+        Statement::Return { return_value } => visitor.visit_expression_mut(return_value),
+        Statement::While { condition, body } => {
+            visitor.visit_expression_mut(condition);
+            visitor.visit_block_mut(body);
+        }
+        Statement::For {
+            target,
+            iterable,
+            body,
+        } => {
+            visitor.visit_pattern_mut(target);
+            visitor.visit_expression_mut(iterable);
+            visitor.visit_block_mut(body);
+        }
+        Statement::Throw { value, .. } => visitor.visit_expression_mut(value),
+        Statement::Export(statement) => visitor.visit_statement_mut(statement),
+        Statement::Expression(expression) => visitor.visit_expression_mut(expression),
+        Statement::Break | Statement::Continue | Statement::Import { .. } => {}
+    }
+}
+
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut Expression) {
+    match expression {
+        Expression::InterpolatedString(parts) => {
+            for part in parts {
+                if let StringPart::Expression(expression) = part {
+                    visitor.visit_expression_mut(expression);
+                }
+            }
+        }
+        Expression::ArrayLiteral(elements) => {
+            for element in elements {
+                visitor.visit_expression_mut(element);
+            }
+        }
+        Expression::HashLiteral(pairs) => {
+            for (key, value) in pairs {
+                visitor.visit_expression_mut(key);
+                visitor.visit_expression_mut(value);
+            }
+        }
+        Expression::Index { left, index, .. } => {
+            visitor.visit_expression_mut(left);
+            visitor.visit_expression_mut(index);
+        }
+        Expression::Prefix { right, .. } => visitor.visit_expression_mut(right),
+        Expression::Infix { left, right, .. } => {
+            visitor.visit_expression_mut(left);
+            visitor.visit_expression_mut(right);
+        }
+        Expression::Assign { target, value, .. } => {
+            visitor.visit_expression_mut(target);
+            visitor.visit_expression_mut(value);
+        }
+        Expression::If {
+            condition,
+            consequence,
+            else_ifs,
+            alternative,
+        } => {
+            visitor.visit_expression_mut(condition);
+            visitor.visit_block_mut(consequence);
+            for (condition, consequence) in else_ifs {
+                visitor.visit_expression_mut(condition);
+                visitor.visit_block_mut(consequence);
+            }
+            if let Some(alternative) = alternative {
+                visitor.visit_block_mut(alternative);
+            }
+        }
+        Expression::Match { value, arms, .. } => {
+            visitor.visit_expression_mut(value);
+            for arm in arms {
+                visitor.visit_match_arm_mut(arm);
+            }
+        }
+        Expression::Try { body, catch, .. } => {
+            visitor.visit_block_mut(body);
+            visitor.visit_block_mut(catch);
+        }
+        Expression::FunctionLiteral { parameters, body } => {
+            for parameter in parameters {
+                visitor.visit_parameter_mut(parameter);
+            }
+            visitor.visit_block_mut(body);
+        }
+        Expression::FunctionCall {
+            function,
+            arguments,
+            named_arguments,
+            ..
+        } => {
+            visitor.visit_expression_mut(function);
+            for argument in arguments {
+                visitor.visit_expression_mut(argument);
+            }
+            for (_, argument) in named_arguments {
+                visitor.visit_expression_mut(argument);
+            }
+        }
+        Expression::Identifier(..)
+        | Expression::IntegerLiteral(_)
+        | Expression::BigIntegerLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::Boolean(_) => {}
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, block: &mut Block) {
+    for statement in &mut block.statements {
+        visitor.visit_statement_mut(statement);
+    }
+}
+
+pub fn walk_pattern_mut<V: VisitorMut + ?Sized>(visitor: &mut V, pattern: &mut Pattern) {
+    if let Pattern::Array(patterns) = pattern {
+        for pattern in patterns {
+            visitor.visit_pattern_mut(pattern);
+        }
+    }
+}
+
+pub fn walk_parameter_mut<V: VisitorMut + ?Sized>(visitor: &mut V, parameter: &mut Parameter) {
+    if let Parameter::Default(_, default) = parameter {
+        visitor.visit_expression_mut(default);
+    }
+}
+
+pub fn walk_match_arm_mut<V: VisitorMut + ?Sized>(visitor: &mut V, arm: &mut MatchArm) {
+    visitor.visit_pattern_mut(&mut arm.pattern);
+    visitor.visit_block_mut(&mut arm.body);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::visit::{
+        walk_expression, walk_expression_mut, walk_pattern, Visitor, VisitorMut,
+    };
+    use crate::ast::{Expression, Pattern, Program};
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse(input: &str) -> Program {
+        Parser::new(Lexer::new(input.chars()))
+            .parse_program()
+            .unwrap()
+    }
+
+    /// Collects every identifier, in the order they are visited
+    #[derive(Default)]
+    struct Identifiers(Vec<String>);
+
+    impl Visitor for Identifiers {
+        fn visit_expression(&mut self, expression: &Expression) {
+            if let Expression::Identifier(name, _) = expression {
+                self.0.push(name.clone());
+            }
+            walk_expression(self, expression);
+        }
+
+        fn visit_pattern(&mut self, pattern: &Pattern) {
+            if let Pattern::Identifier(name) = pattern {
+                self.0.push(format!("pattern {name}"));
+            }
+            walk_pattern(self, pattern);
+        }
+    }
+
+    #[test]
+    fn visitor_reaches_every_expression() {
+        let program = parse(
+            r#"export let [x, _] = [a, {b: c}];
+            let f = fn(p = d, ...rest) { return e[g]; };
+            while (h) { for (i in j) { k += -l; break; } }
+            if (m) { n } else if (o) { q } else { r };
+            match (s) { [t] => u };
+            try { throw v; } catch (err) { w };
+            y(z, named: "${aa} and ${bb}");"#,
+        );
+        let mut identifiers = Identifiers::default();
+        identifiers.visit_program(&program);
+        assert_eq!(
+            identifiers.0.join(" "),
+            "pattern x a b c pattern f d e g h pattern i j k l m n o q r s pattern t u v w y z aa bb"
+        );
+    }
+
+    /// Folds additions of integer literals into a single literal
+    struct FoldAdditions;
+
+    impl VisitorMut for FoldAdditions {
+        fn visit_expression_mut(&mut self, expression: &mut Expression) {
+            // children first so nested additions fold from the inside out
+            walk_expression_mut(self, expression);
+            if let Expression::Infix {
+                left,
+                operator,
+                right,
+                ..
+            } = expression
+            {
+                if let (Expression::IntegerLiteral(left), "+", Expression::IntegerLiteral(right)) =
+                    (left.as_ref(), operator.as_str(), right.as_ref())
+                {
+                    *expression = Expression::IntegerLiteral(left + right);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn visitor_mut_rewrites_in_place() {
+        let mut program = parse("let x = 1 + 2 + 3; fn(a = 4 + 5) { [a + 1, 2 + 2 * 3] }");
+        FoldAdditions.visit_program_mut(&mut program);
+        assert_eq!(
+            program.to_string(),
+            "let x = 6;\nfn(a = 9){[(a + 1), (2 + (2 * 3))];};"
+        );
+    }
+}
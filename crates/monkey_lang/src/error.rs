@@ -1,5 +1,16 @@
+use crate::evaluator::limits::Limit;
+use crate::object::Object;
+use crate::token::Span;
+use std::fmt::{Display, Formatter};
 use thiserror::Error;
 
+/// Problems found while parsing that don't stop the program from running
+#[derive(Error, Debug, PartialEq)]
+pub enum Warning {
+    #[error("non-exhaustive match: {0} not covered")]
+    NonExhaustiveMatch(String),
+}
+
 #[derive(Error, Debug, PartialEq)]
 pub enum Error {
     // TODO: add the expected token to this
@@ -11,4 +22,173 @@ pub enum Error {
     InvalidIntegerValue(String),
     #[error("failed to convert {0} to boolean value")]
     InvalidBooleanValue(String),
+    #[error("nested deeper than {0} levels")]
+    NestedTooDeeply(usize),
+    #[error("{0} outside of a loop")]
+    OutsideLoop(String),
+    #[error("export is only allowed at the top level of a module")]
+    ExportNotAtTopLevel,
+    #[error("import is only allowed at the top level of a program or module")]
+    ImportNotAtTopLevel,
+    /// A return or tail call that got out of the function it belongs to, only
+    /// possible in a program built by hand
+    #[error("unexpected {0}")]
+    UnexpectedControlFlow(String),
+    #[error("refutable pattern in binding: {0}")]
+    RefutablePattern(String),
+    #[error("cannot assign to builtin {0}, shadow it with let instead")]
+    AssignToBuiltin(String),
+    #[error("invalid assignment target: {0}")]
+    InvalidAssignmentTarget(String),
+    #[error("identifier not found: {0}")]
+    IdentifierNotFound(String),
+    #[error("type mismatch: {0}")]
+    TypeMismatch(String),
+    #[error("unknown operator: {0}")]
+    UnknownOperator(String),
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("modulo by zero")]
+    ModuloByZero,
+    #[error("integer overflow: {0}")]
+    IntegerOverflow(String),
+    #[error("not a function: {0}")]
+    NotAFunction(String),
+    #[error("unusable as hash key: {0}")]
+    UnusableHashKey(String),
+    #[error("index {index} out of bounds for array of length {length}")]
+    IndexOutOfBounds { index: i64, length: usize },
+    #[error("key not found: {0}")]
+    KeyNotFound(String),
+    #[error("no match arm for value: {0}")]
+    NoMatchingArm(String),
+    #[error("index operator not supported: {0}")]
+    IndexNotSupported(String),
+    #[error("{0} is not iterable")]
+    NotIterable(String),
+    #[error("cannot destructure {value} with pattern {pattern}")]
+    CannotDestructure { pattern: String, value: String },
+    #[error("value does not match pattern {0}")]
+    PatternMismatch(String),
+    #[error("length mismatch: expected {expected} elements, got {got}")]
+    LengthMismatch { expected: usize, got: usize },
+    #[error("length mismatch: expected at least {expected} elements, got {got}")]
+    NotEnoughElements { expected: usize, got: usize },
+    #[error("missing argument for parameter {0}")]
+    MissingArgument(String),
+    #[error("unknown parameter: {0}")]
+    UnknownParameter(String),
+    #[error("duplicate parameter: {0}")]
+    DuplicateParameter(String),
+    #[error("multiple values for parameter {0}")]
+    DuplicateArgument(String),
+    #[error("positional argument after named arguments: {0}")]
+    PositionalAfterNamedArgument(String),
+    #[error("argument to {function} not supported, got {got}")]
+    UnsupportedArgument { function: String, got: String },
+    #[error("argument to {function} out of range, got {got}")]
+    ArgumentOutOfRange { function: String, got: String },
+    #[error("permission denied: {0}")]
+    PermissionDenied(String),
+    #[error("failed to access {path}: {message}")]
+    IoFailed { path: String, message: String },
+    #[error("invalid JSON at byte {offset}: {message}")]
+    InvalidJson { message: String, offset: usize },
+    #[error("duplicate key in JSON object: {0}")]
+    DuplicateJsonKey(String),
+    #[error("wrong number of arguments: expected {expected}, got {got}")]
+    WrongNumberOfArguments { expected: usize, got: usize },
+    /// A value raised by a throw statement
+    #[error("{0}")]
+    Thrown(Object),
+    #[error("module not found: {0}")]
+    ModuleNotFound(String),
+    #[error("failed to load module {path}: {message}")]
+    ModuleLoadFailed { path: String, message: String },
+    #[error("import cycle: {0}")]
+    ImportCycle(String),
+    /// Raised when a program goes over one of the evaluator's limits
+    #[error("limit exceeded: {0}")]
+    LimitExceeded(Limit),
+    #[error("execution cancelled")]
+    Cancelled,
+}
+
+impl Error {
+    /// Limit and cancellation errors can't be caught, a program could otherwise
+    /// keep running after using up its budget or being told to stop
+    pub(crate) fn is_catchable(&self) -> bool {
+        !matches!(self, Error::LimitExceeded(_) | Error::Cancelled)
+    }
+}
+
+/// An error raised while evaluating a program, along with where it happened
+#[derive(Debug, PartialEq)]
+pub struct RuntimeError {
+    pub(crate) error: Error,
+    /// Span of the innermost expression the error came from
+    pub(crate) span: Option<Span>,
+    /// Calls to monkey functions that were running, innermost first
+    pub(crate) stack: Vec<Frame>,
+}
+
+impl RuntimeError {
+    /// What went wrong, match on it to tell limit, cancellation and script errors apart
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn stack(&self) -> &[Frame] {
+        &self.stack
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+impl From<Error> for RuntimeError {
+    fn from(error: Error) -> Self {
+        Self {
+            error,
+            span: None,
+            stack: Vec::new(),
+        }
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.error.to_string())?;
+        if let Some(span) = self.span {
+            f.write_str(&format!(" at {span}"))?;
+        }
+        for frame in &self.stack {
+            f.write_str(&format!(
+                "\n    in {} called at {}",
+                frame.function, frame.call_site
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+/// A function call in the stack of a runtime error
+#[derive(Debug, PartialEq, Clone)]
+pub struct Frame {
+    pub(crate) function: String,
+    pub(crate) call_site: Span,
+}
+
+impl Frame {
+    /// Name the function was called by, <anonymous> if it had none
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    pub fn call_site(&self) -> Span {
+        self.call_site
+    }
 }
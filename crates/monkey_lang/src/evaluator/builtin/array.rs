@@ -0,0 +1,320 @@
+use crate::error::Error;
+use crate::evaluator::builtin::{expect_arguments, unsupported_argument};
+use crate::evaluator::{EvalResult, Evaluator, Unwind};
+use crate::object::{Builtin, Object};
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+/// Builtins that call a function for each element of an array, string or
+/// hash, going through them the same way a for loop does. The calls are made
+/// one after another from Rust, so they don't add up on the call stack
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "map",
+        function: map,
+    },
+    Builtin {
+        name: "filter",
+        function: filter,
+    },
+    Builtin {
+        name: "reduce",
+        function: reduce,
+    },
+    Builtin {
+        name: "sort_by",
+        function: sort_by,
+    },
+    Builtin {
+        name: "any",
+        function: any,
+    },
+    Builtin {
+        name: "all",
+        function: all,
+    },
+    Builtin {
+        name: "find",
+        function: find,
+    },
+];
+
+fn function_argument(function: &str, argument: Object) -> Result<Object, Error> {
+    match argument {
+        Object::Function(_) | Object::Builtin(_) => Ok(argument),
+        other => Err(unsupported_argument(function, &other)),
+    }
+}
+
+/// Splits the arguments into the elements to go through and the function to call
+fn elements_and_function(
+    function: &str,
+    arguments: Vec<Object>,
+) -> Result<(Vec<Object>, Object), Error> {
+    let [items, callback] = expect_arguments(arguments)?;
+    Ok((items.elements()?, function_argument(function, callback)?))
+}
+
+fn call(evaluator: &mut Evaluator, function: &Object, arguments: Vec<Object>) -> EvalResult {
+    evaluator.apply_function(function.clone(), arguments, Vec::new())
+}
+
+/// The results of calling f with each element
+fn map(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let (elements, f) = elements_and_function("map", arguments)?;
+    let results = elements
+        .into_iter()
+        .map(|element| {
+            let result = call(evaluator, &f, vec![element])?;
+            evaluator.check_depth(&result, 1)?;
+            Ok(result)
+        })
+        .collect::<Result<Vec<Object>, Unwind>>()?;
+    Ok(Object::Array(Rc::new(results)))
+}
+
+/// The elements the predicate returns a truthy value for
+fn filter(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let (elements, predicate) = elements_and_function("filter", arguments)?;
+    let mut results = Vec::new();
+    for element in elements {
+        if call(evaluator, &predicate, vec![element.clone()])?.is_truthy() {
+            // the pairs of a hash are one level deeper than its values
+            evaluator.check_depth(&element, 1)?;
+            results.push(element);
+        }
+    }
+    Ok(Object::Array(Rc::new(results)))
+}
+
+/// Combines the elements from first to last, f gets the result so far
+/// (starting with initial) and the next element
+fn reduce(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [items, initial, f] = expect_arguments(arguments)?;
+    let f = function_argument("reduce", f)?;
+    items
+        .elements()?
+        .into_iter()
+        .try_fold(initial, |accumulator, element| {
+            call(evaluator, &f, vec![accumulator, element])
+        })
+}
+
+/// Sorts the elements by the key f returns for each of them, keys have to be
+/// all integers or all strings. Elements with equal keys keep their order
+fn sort_by(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let (elements, f) = elements_and_function("sort_by", arguments)?;
+    let mut keyed = elements
+        .into_iter()
+        .map(|element| Ok((call(evaluator, &f, vec![element.clone()])?, element)))
+        .collect::<Result<Vec<(Object, Object)>, Unwind>>()?;
+
+    if let Some(((first, _), rest)) = keyed.split_first() {
+        if !matches!(
+            first,
+            Object::Integer(_) | Object::BigInteger(_) | Object::String(_)
+        ) {
+            return Err(unsupported_argument("sort_by", first).into());
+        }
+        if let Some((key, _)) = rest
+            .iter()
+            .find(|(key, _)| key.type_name() != first.type_name())
+        {
+            return Err(Error::TypeMismatch(format!(
+                "{} < {}",
+                first.type_name(),
+                key.type_name()
+            ))
+            .into());
+        }
+    }
+    for (_, element) in &keyed {
+        evaluator.check_depth(element, 1)?;
+    }
+    keyed.sort_by(|(left, _), (right, _)| compare_keys(left, right));
+    Ok(Object::Array(Rc::new(
+        keyed.into_iter().map(|(_, element)| element).collect(),
+    )))
+}
+
+/// Orders keys of the same type, sort_by checked they are integers or strings
+fn compare_keys(left: &Object, right: &Object) -> Ordering {
+    match (left, right) {
+        (Object::Integer(left), Object::Integer(right)) => left.cmp(right),
+        (Object::String(left), Object::String(right)) => left.cmp(right),
+        (left, right) => left.to_big_integer().cmp(&right.to_big_integer()),
+    }
+}
+
+/// Whether the predicate returns a truthy value for any element, stops at
+/// the first one it does
+fn any(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let (elements, predicate) = elements_and_function("any", arguments)?;
+    for element in elements {
+        if call(evaluator, &predicate, vec![element])?.is_truthy() {
+            return Ok(Object::Boolean(true));
+        }
+    }
+    Ok(Object::Boolean(false))
+}
+
+/// Whether the predicate returns a truthy value for every element, stops at
+/// the first one it doesn't
+fn all(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let (elements, predicate) = elements_and_function("all", arguments)?;
+    for element in elements {
+        if !call(evaluator, &predicate, vec![element])?.is_truthy() {
+            return Ok(Object::Boolean(false));
+        }
+    }
+    Ok(Object::Boolean(true))
+}
+
+/// The first element the predicate returns a truthy value for, null if
+/// there is none
+fn find(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let (elements, predicate) = elements_and_function("find", arguments)?;
+    for element in elements {
+        if call(evaluator, &predicate, vec![element.clone()])?.is_truthy() {
+            return Ok(element);
+        }
+    }
+    Ok(Object::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::evaluator::limits::{Limit, Limits};
+    use crate::evaluator::tests::{eval_input, eval_input_with_options};
+    use crate::evaluator::Options;
+
+    #[test]
+    fn eval_array_builtins() {
+        for (input, expected) in [
+            ("map([1, 2, 3], fn(x) { x * 2 })", "[2, 4, 6]"),
+            (r#"map("ab", upper)"#, r#"["A", "B"]"#),
+            (r#"map({"a": 1}, fn(pair) { pair[1] })"#, "[1]"),
+            ("filter(range(0, 10), fn(x) { x % 3 == 0 })", "[0, 3, 6, 9]"),
+            ("filter([1, 2], fn(x) { false })", "[]"),
+            ("reduce([1, 2, 3, 4], 0, fn(a, b) { a + b })", "10"),
+            ("reduce([], 5, fn(a, b) { a + b })", "5"),
+            (r#"reduce(["a", "b"], "", fn(a, b) { b + a })"#, r#""ba""#),
+            (
+                r#"sort_by([{"n": 3, "k": "a"}, {"n": 1, "k": "b"}, {"n": 3, "k": "c"}], fn(x) { x["n"] })"#,
+                r#"[{"k": "b", "n": 1}, {"k": "a", "n": 3}, {"k": "c", "n": 3}]"#,
+            ),
+            (
+                r#"sort_by(["pear", "fig", "apple"], fn(x) { x })"#,
+                r#"["apple", "fig", "pear"]"#,
+            ),
+            (
+                "sort_by([2, -1, 1], fn(x) { x * pow(10, 30) })",
+                "[-1, 1, 2]",
+            ),
+            ("sort_by([], fn(x) { x })", "[]"),
+            (
+                "[any([1, 2], fn(x) { x > 1 }), any([], fn(x) { true })]",
+                "[true, false]",
+            ),
+            (
+                "[all([1, 2], fn(x) { x > 1 }), all([], fn(x) { false })]",
+                "[false, true]",
+            ),
+            (
+                "[find([1, 2, 3], fn(x) { x > 1 }), find([1], fn(x) { x > 1 })]",
+                "[2, null]",
+            ),
+        ] {
+            assert_eq!(
+                eval_input(&format!("[{input}]")).map(|value| value.to_string()),
+                Ok(format!("[{expected}]")),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn array_builtins_stop_early() {
+        for (input, expected) in [
+            ("any([1, 2, 3], fn(x) { calls += 1; x == 2 })", "[true, 2]"),
+            ("all([1, 2, 3], fn(x) { calls += 1; x < 2 })", "[false, 2]"),
+            ("find([1, 2, 3], fn(x) { calls += 1; x == 1 })", "[1, 1]"),
+        ] {
+            assert_eq!(
+                eval_input(&format!(
+                    "let calls = 0; let result = {input}; [result, calls]"
+                ))
+                .map(|value| value.to_string()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn array_builtins_do_not_use_call_depth_per_element() {
+        let options = Options {
+            limits: Limits {
+                max_call_depth: Some(4),
+                ..Limits::default()
+            },
+            ..Options::default()
+        };
+        assert_eq!(
+            eval_input_with_options(
+                "let items = []; let i = 0; while (i < 5000) { items = push(items, i); i += 1; }; \
+                len(filter(map(items, fn(x) { x + 1 }), fn(x) { x % 2 == 0 }))",
+                options.clone()
+            )
+            .map(|value| value.to_string()),
+            Ok("2500".to_string())
+        );
+        // callbacks calling back into map still nest, as long as they are not tail calls
+        assert_eq!(
+            eval_input_with_options(
+                "map([1], fn(a) { [map([1], fn(b) { [map([1], fn(c) { [map([1], fn(d) { [map([1], fn(e) { e })] })] })] })] })",
+                options
+            ),
+            Err(Error::LimitExceeded(Limit::CallDepth(4)))
+        );
+    }
+
+    #[test]
+    fn eval_array_builtin_errors() {
+        for (input, expected) in [
+            (
+                "map([1], 1)",
+                Error::UnsupportedArgument {
+                    function: "map".to_string(),
+                    got: "INTEGER".to_string(),
+                },
+            ),
+            (
+                "filter(1, fn(x) { x })",
+                Error::NotIterable("INTEGER".to_string()),
+            ),
+            (
+                "reduce([1], 0)",
+                Error::WrongNumberOfArguments {
+                    expected: 3,
+                    got: 2,
+                },
+            ),
+            ("map([1, 0], fn(x) { 1 / x })", Error::DivisionByZero),
+            (
+                r#"sort_by([1, "a"], fn(x) { x })"#,
+                Error::TypeMismatch("INTEGER < STRING".to_string()),
+            ),
+            (
+                "sort_by([[1]], fn(x) { x })",
+                Error::UnsupportedArgument {
+                    function: "sort_by".to_string(),
+                    got: "ARRAY".to_string(),
+                },
+            ),
+        ] {
+            assert_eq!(eval_input(input), Err(expected), "{input}");
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use crate::error::Error;
+use crate::evaluator::builtin::{expect_arguments, unsupported_argument};
+use crate::evaluator::capabilities::Capabilities;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::{Builtin, Object};
+use std::fs;
+use std::rc::Rc;
+
+const READ_FILE: Builtin = Builtin {
+    name: "read_file",
+    function: read_file,
+};
+
+const LIST_DIR: Builtin = Builtin {
+    name: "list_dir",
+    function: list_dir,
+};
+
+const WRITE_FILE: Builtin = Builtin {
+    name: "write_file",
+    function: write_file,
+};
+
+const ENV_VAR: Builtin = Builtin {
+    name: "env_var",
+    function: env_var,
+};
+
+/// Builtins that reach outside the evaluator, for the capabilities the host granted
+pub(super) fn granted(capabilities: &Capabilities) -> impl Iterator<Item = Builtin> {
+    [
+        (READ_FILE, !capabilities.read.is_empty()),
+        (LIST_DIR, !capabilities.read.is_empty()),
+        (WRITE_FILE, !capabilities.write.is_empty()),
+        (ENV_VAR, !capabilities.env_vars.is_empty()),
+    ]
+    .into_iter()
+    .filter_map(|(builtin, granted)| granted.then_some(builtin))
+}
+
+fn string_argument(function: &str, argument: &Object) -> Result<Rc<str>, Error> {
+    match argument {
+        Object::String(value) => Ok(value.clone()),
+        other => Err(unsupported_argument(function, other)),
+    }
+}
+
+fn io_failed(path: &str, error: std::io::Error) -> Error {
+    Error::IoFailed {
+        path: path.to_string(),
+        message: error.to_string(),
+    }
+}
+
+/// Contents of a UTF-8 text file
+fn read_file(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [path] = expect_arguments(arguments)?;
+    let path = string_argument("read_file", &path)?;
+    let resolved = evaluator.capabilities.readable_path(&path)?;
+    let contents = fs::read_to_string(resolved).map_err(|error| io_failed(&path, error))?;
+    Ok(Object::String(contents.into()))
+}
+
+/// Names of the entries in a directory, sorted
+fn list_dir(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [path] = expect_arguments(arguments)?;
+    let path = string_argument("list_dir", &path)?;
+    let resolved = evaluator.capabilities.readable_path(&path)?;
+
+    let mut names = fs::read_dir(resolved)
+        .and_then(|entries| {
+            entries
+                .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+                .collect::<Result<Vec<String>, std::io::Error>>()
+        })
+        .map_err(|error| io_failed(&path, error))?;
+    names.sort();
+    Ok(Object::Array(Rc::new(
+        names
+            .into_iter()
+            .map(|name| Object::String(name.into()))
+            .collect(),
+    )))
+}
+
+/// Replaces the contents of a file with a string, creating the file if needed
+fn write_file(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [path, contents] = expect_arguments(arguments)?;
+    let path = string_argument("write_file", &path)?;
+    let contents = string_argument("write_file", &contents)?;
+    let resolved = evaluator.capabilities.writable_path(&path)?;
+    fs::write(resolved, contents.as_bytes()).map_err(|error| io_failed(&path, error))?;
+    Ok(Object::Null)
+}
+
+/// Value of an environment variable, null when it isn't set
+fn env_var(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [name] = expect_arguments(arguments)?;
+    let name = string_argument("env_var", &name)?;
+    evaluator.capabilities.check_env_var(&name)?;
+    Ok(std::env::var(&*name).map_or(Object::Null, |value| Object::String(value.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::quote_string;
+    use crate::error::Error;
+    use crate::evaluator::capabilities::Capabilities;
+    use crate::evaluator::tests::{eval_input, eval_input_with_options};
+    use crate::evaluator::Options;
+    use crate::object::Object;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// A fresh directory under the system temp directory, with allowed and
+    /// private subdirectories
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("monkey_io_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("allowed/sub")).unwrap();
+        fs::create_dir_all(dir.join("private")).unwrap();
+        fs::write(dir.join("allowed/a.txt"), "alpha").unwrap();
+        fs::write(dir.join("allowed/sub/b.txt"), "beta").unwrap();
+        fs::write(dir.join("private/secret.txt"), "secret").unwrap();
+        dir
+    }
+
+    fn eval_with_capabilities(input: &str, capabilities: Capabilities) -> Result<Object, Error> {
+        eval_input_with_options(
+            input,
+            Options {
+                capabilities,
+                ..Options::default()
+            },
+        )
+    }
+
+    fn path_literal(path: &Path) -> String {
+        quote_string(&path.to_string_lossy())
+    }
+
+    #[test]
+    fn io_builtins_need_capabilities() {
+        for name in ["read_file", "list_dir", "write_file", "env_var"] {
+            assert_eq!(
+                eval_input(name),
+                Err(Error::IdentifierNotFound(name.to_string()))
+            );
+        }
+
+        // granting one capability doesn't install the builtins of the others
+        let capabilities = Capabilities {
+            read: vec![std::env::temp_dir()],
+            ..Capabilities::default()
+        };
+        assert_eq!(
+            eval_with_capabilities("write_file", capabilities.clone()),
+            Err(Error::IdentifierNotFound("write_file".to_string()))
+        );
+        assert_eq!(
+            eval_with_capabilities("env_var", capabilities),
+            Err(Error::IdentifierNotFound("env_var".to_string()))
+        );
+    }
+
+    #[test]
+    fn eval_read_builtins() {
+        let dir = temp_dir("read");
+        let capabilities = Capabilities {
+            read: vec![dir.join("allowed")],
+            ..Capabilities::default()
+        };
+        let allowed = path_literal(&dir.join("allowed"));
+        let input = format!(
+            "[read_file({allowed} + \"/a.txt\"), read_file({allowed} + \"/sub/../sub/b.txt\"), list_dir({allowed})]"
+        );
+        assert_eq!(
+            eval_with_capabilities(&input, capabilities.clone()).map(|value| value.to_string()),
+            Ok(r#"["alpha", "beta", ["a.txt", "sub"]]"#.to_string())
+        );
+
+        for path in [
+            dir.join("private/secret.txt"),
+            dir.join("allowed/../private/secret.txt"),
+            dir.join("private/missing.txt"),
+            dir.join("allowed/missing/../../private/secret.txt"),
+        ] {
+            let path = path.to_string_lossy().into_owned();
+            assert_eq!(
+                eval_with_capabilities(
+                    &format!("read_file({})", quote_string(&path)),
+                    capabilities.clone()
+                ),
+                Err(Error::PermissionDenied(path.clone())),
+                "{path}"
+            );
+        }
+
+        let missing = dir
+            .join("allowed/missing.txt")
+            .to_string_lossy()
+            .into_owned();
+        assert!(matches!(
+            eval_with_capabilities(
+                &format!("read_file({})", quote_string(&missing)),
+                capabilities
+            ),
+            Err(Error::IoFailed { path, .. }) if path == missing
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_cannot_leave_allowed_paths() {
+        let dir = temp_dir("symlink");
+        std::os::unix::fs::symlink(dir.join("private"), dir.join("allowed/link")).unwrap();
+        let capabilities = Capabilities {
+            read: vec![dir.join("allowed")],
+            write: vec![dir.join("allowed")],
+            ..Capabilities::default()
+        };
+        for input in [
+            format!(
+                "read_file({})",
+                path_literal(&dir.join("allowed/link/secret.txt"))
+            ),
+            format!(
+                "write_file({}, \"x\")",
+                path_literal(&dir.join("allowed/link/new.txt"))
+            ),
+        ] {
+            assert!(
+                matches!(
+                    eval_with_capabilities(&input, capabilities.clone()),
+                    Err(Error::PermissionDenied(_))
+                ),
+                "{input}"
+            );
+        }
+        assert!(!dir.join("private/new.txt").exists());
+    }
+
+    #[test]
+    fn eval_write_file() {
+        let dir = temp_dir("write");
+        let capabilities = Capabilities {
+            read: vec![dir.join("allowed")],
+            write: vec![dir.join("allowed/sub")],
+            ..Capabilities::default()
+        };
+        let new_file = path_literal(&dir.join("allowed/sub/new.txt"));
+        assert_eq!(
+            eval_with_capabilities(
+                &format!("write_file({new_file}, \"hello\"); read_file({new_file})"),
+                capabilities.clone()
+            ),
+            Ok(Object::String("hello".into()))
+        );
+
+        // readable isn't writable
+        let path = dir.join("allowed/a.txt").to_string_lossy().into_owned();
+        assert_eq!(
+            eval_with_capabilities(
+                &format!("write_file({}, \"x\")", quote_string(&path)),
+                capabilities.clone()
+            ),
+            Err(Error::PermissionDenied(path))
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("allowed/a.txt")).unwrap(),
+            "alpha"
+        );
+
+        assert_eq!(
+            eval_with_capabilities(&format!("write_file({new_file}, 1)"), capabilities),
+            Err(Error::UnsupportedArgument {
+                function: "write_file".to_string(),
+                got: "INTEGER".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn eval_env_var() {
+        let capabilities = Capabilities {
+            env_vars: vec!["PATH".to_string(), "MONKEY_UNSET_VARIABLE".to_string()],
+            ..Capabilities::default()
+        };
+        assert_eq!(
+            eval_with_capabilities(r#"env_var("PATH")"#, capabilities.clone()),
+            Ok(std::env::var("PATH").map_or(Object::Null, |value| Object::String(value.into())))
+        );
+        assert_eq!(
+            eval_with_capabilities(r#"env_var("MONKEY_UNSET_VARIABLE")"#, capabilities.clone()),
+            Ok(Object::Null)
+        );
+        assert_eq!(
+            eval_with_capabilities(r#"env_var("HOME")"#, capabilities),
+            Err(Error::PermissionDenied("HOME".to_string()))
+        );
+    }
+}
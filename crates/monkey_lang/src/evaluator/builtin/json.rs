@@ -0,0 +1,580 @@
+use crate::error::Error;
+use crate::evaluator::builtin::{
+    expect_arguments, expect_arguments_with_default, unsupported_argument,
+};
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::{Builtin, HashKey, Object};
+use num_bigint::BigInt;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Builtins for converting values to and from JSON text, objects map to
+/// hashes and arrays to arrays. Monkey only has integers, so numbers with a
+/// fraction or exponent are rejected
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "json_parse",
+        function: json_parse,
+    },
+    Builtin {
+        name: "json_stringify",
+        function: json_stringify,
+    },
+];
+
+/// Deepest nesting of arrays and objects json_parse accepts, parsing
+/// recurses so deeper input could overflow the stack
+const MAX_DEPTH: usize = 256;
+
+/// Widest indent json_stringify uses, larger ones are clamped to it
+const MAX_INDENT: usize = 10;
+
+/// Parses a JSON document into the value it represents. Numbers with a
+/// fraction or exponent, e.g. 12.5 or 1e3, are an error at their offset
+/// since they can't be represented without losing precision or their type
+fn json_parse(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [text] = expect_arguments(arguments)?;
+    let Object::String(text) = &text else {
+        return Err(unsupported_argument("json_parse", &text).into());
+    };
+
+    let mut parser = JsonParser {
+        text,
+        position: 0,
+        depth: 0,
+    };
+    parser.skip_whitespace();
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.position < text.len() {
+        return Err(parser.unexpected_character().into());
+    }
+    evaluator.check_depth(&value, 0)?;
+    Ok(value)
+}
+
+/// Converts a value to JSON text, compact unless indent is the number of
+/// spaces to indent nested values by. Hash keys that aren't strings are
+/// written as the string they display as, a hash with both 1 and "1" as keys
+/// is an error since the object would have the same key twice
+fn json_stringify(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value, indent] = expect_arguments_with_default(arguments, Object::Integer(0))?;
+    let indent = match indent {
+        Object::Integer(indent) => {
+            usize::try_from(indent).map_err(|_| Error::ArgumentOutOfRange {
+                function: "json_stringify".to_string(),
+                got: indent.to_string(),
+            })?
+        }
+        other => return Err(unsupported_argument("json_stringify", &other).into()),
+    };
+
+    let mut output = String::new();
+    write_json(&mut output, &value, indent.min(MAX_INDENT))?;
+    Ok(Object::String(output.into()))
+}
+
+struct JsonParser<'a> {
+    text: &'a str,
+    /// Byte offset of the next character
+    position: usize,
+    /// Arrays and objects currently being parsed
+    depth: usize,
+}
+
+impl JsonParser<'_> {
+    fn error(&self, message: impl Into<String>) -> Error {
+        Error::InvalidJson {
+            message: message.into(),
+            offset: self.position,
+        }
+    }
+
+    fn unexpected_character(&self) -> Error {
+        match self.peek_char() {
+            Some(char_value) => self.error(format!("unexpected character {char_value:?}")),
+            None => self.error("unexpected end of input"),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.position).copied()
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    /// Moves past the expected byte, or fails without moving
+    fn expect(&mut self, expected: u8) -> Result<(), Error> {
+        if self.peek() != Some(expected) {
+            return Err(self.unexpected_character());
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn parse_value(&mut self) -> Result<Object, Error> {
+        match self.peek() {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => Ok(Object::String(self.parse_string()?.into())),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(b't') => self.parse_keyword("true", Object::Boolean(true)),
+            Some(b'f') => self.parse_keyword("false", Object::Boolean(false)),
+            Some(b'n') => self.parse_keyword("null", Object::Null),
+            _ => Err(self.unexpected_character()),
+        }
+    }
+
+    fn parse_keyword(&mut self, keyword: &str, value: Object) -> Result<Object, Error> {
+        if !self.text[self.position..].starts_with(keyword) {
+            return Err(self.error(format!("expected {keyword}")));
+        }
+        self.position += keyword.len();
+        Ok(value)
+    }
+
+    /// Integers of any size, numbers that would need a float are an error
+    fn parse_number(&mut self) -> Result<Object, Error> {
+        let start = self.position;
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        match self.peek() {
+            // no leading zeros
+            Some(b'0') => self.position += 1,
+            Some(b'1'..=b'9') => {
+                while let Some(b'0'..=b'9') = self.peek() {
+                    self.position += 1;
+                }
+            }
+            _ => return Err(self.error("expected a digit")),
+        }
+        if let Some(b'.' | b'e' | b'E') = self.peek() {
+            return Err(self.error("numbers with a fraction or exponent are not supported"));
+        }
+
+        let value: BigInt = self.text[start..self.position]
+            .parse()
+            .expect("digits should parse as an integer");
+        Ok(Object::from(value))
+    }
+
+    fn parse_string(&mut self) -> Result<String, Error> {
+        self.expect(b'"')?;
+        let mut value = String::new();
+        loop {
+            let Some(char_value) = self.peek_char() else {
+                return Err(self.error("unterminated string"));
+            };
+            match char_value {
+                '"' => {
+                    self.position += 1;
+                    return Ok(value);
+                }
+                '\\' => {
+                    self.position += 1;
+                    value.push(self.parse_escape()?);
+                }
+                '\u{0}'..='\u{1f}' => return Err(self.error("control character in string")),
+                other => {
+                    self.position += other.len_utf8();
+                    value.push(other);
+                }
+            }
+        }
+    }
+
+    /// The character an escape sequence stands for, starting after the backslash
+    fn parse_escape(&mut self) -> Result<char, Error> {
+        let escaped = match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                let start = self.position - 1;
+                self.position += 1;
+                let unit = self.parse_hex_unit()?;
+                // characters outside the basic plane are written as a
+                // surrogate pair of two escapes
+                let code_point = if (0xD800..0xDC00).contains(&unit)
+                    && self.text[self.position..].starts_with("\\u")
+                {
+                    self.position += 2;
+                    let low = self.parse_hex_unit()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        self.position = start;
+                        return Err(self.error("invalid surrogate pair"));
+                    }
+                    0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)
+                } else {
+                    unit
+                };
+                return char::from_u32(code_point).ok_or_else(|| {
+                    self.position = start;
+                    self.error("unpaired surrogate")
+                });
+            }
+            _ => return Err(self.error("invalid escape")),
+        };
+        self.position += 1;
+        Ok(escaped)
+    }
+
+    fn parse_hex_unit(&mut self) -> Result<u32, Error> {
+        let digits = self
+            .text
+            .get(self.position..self.position + 4)
+            .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("expected 4 hex digits"))?;
+        self.position += 4;
+        Ok(u32::from_str_radix(digits, 16).expect("hex digits should parse"))
+    }
+
+    fn enter(&mut self) -> Result<(), Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(format!("nested deeper than {MAX_DEPTH} levels")));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn parse_array(&mut self) -> Result<Object, Error> {
+        self.enter()?;
+        self.expect(b'[')?;
+        let mut elements = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+        } else {
+            loop {
+                self.skip_whitespace();
+                elements.push(self.parse_value()?);
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(b',') => self.position += 1,
+                    Some(b']') => {
+                        self.position += 1;
+                        break;
+                    }
+                    _ => return Err(self.unexpected_character()),
+                }
+            }
+        }
+        self.depth -= 1;
+        Ok(Object::Array(Rc::new(elements)))
+    }
+
+    /// Later values win when a key is repeated
+    fn parse_object(&mut self) -> Result<Object, Error> {
+        self.enter()?;
+        self.expect(b'{')?;
+        let mut pairs = BTreeMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+        } else {
+            loop {
+                self.skip_whitespace();
+                let key = self.parse_string()?;
+                self.skip_whitespace();
+                self.expect(b':')?;
+                self.skip_whitespace();
+                pairs.insert(HashKey::String(key.into()), self.parse_value()?);
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(b',') => self.position += 1,
+                    Some(b'}') => {
+                        self.position += 1;
+                        break;
+                    }
+                    _ => return Err(self.unexpected_character()),
+                }
+            }
+        }
+        self.depth -= 1;
+        Ok(Object::Hash(Rc::new(pairs)))
+    }
+}
+
+/// What is left to write of a value being converted to JSON
+enum Pending<'a> {
+    /// A value nested level deep
+    Value(&'a Object, usize),
+    /// The separator before the element at index of a collection
+    Separator(usize, usize),
+    Key(&'a HashKey),
+    /// The closing bracket of a collection nested level deep
+    Closing(char, bool, usize),
+}
+
+/// Writes nested values from a list instead of recursing, so deeply nested
+/// values can't overflow the stack
+fn write_json(output: &mut String, value: &Object, indent: usize) -> Result<(), Error> {
+    let mut pending = vec![Pending::Value(value, 0)];
+    while let Some(next) = pending.pop() {
+        match next {
+            Pending::Value(value, level) => match value {
+                Object::Null => output.push_str("null"),
+                Object::Boolean(_) | Object::Integer(_) | Object::BigInteger(_) => {
+                    output.push_str(&value.to_string())
+                }
+                Object::String(value) => write_json_string(output, value),
+                Object::Array(elements) => {
+                    output.push('[');
+                    pending.push(Pending::Closing(']', !elements.is_empty(), level));
+                    for (index, element) in elements.iter().enumerate().rev() {
+                        pending.push(Pending::Value(element, level + 1));
+                        pending.push(Pending::Separator(index, level + 1));
+                    }
+                }
+                Object::Hash(pairs) => {
+                    if let Some(key) = pairs.keys().find(|key| {
+                        !matches!(key, HashKey::String(_))
+                            && pairs.contains_key(&HashKey::String(json_key(key)))
+                    }) {
+                        return Err(Error::DuplicateJsonKey(json_key(key).to_string()));
+                    }
+                    output.push('{');
+                    pending.push(Pending::Closing('}', !pairs.is_empty(), level));
+                    for (index, (key, value)) in pairs.iter().enumerate().rev() {
+                        pending.push(Pending::Value(value, level + 1));
+                        pending.push(Pending::Key(key));
+                        pending.push(Pending::Separator(index, level + 1));
+                    }
+                }
+                Object::Function(_) | Object::Builtin(_) => {
+                    return Err(unsupported_argument("json_stringify", value))
+                }
+            },
+            Pending::Separator(index, level) => write_separator(output, index, indent, level),
+            Pending::Key(key) => {
+                write_json_string(output, &json_key(key));
+                output.push_str(if indent > 0 { ": " } else { ":" });
+            }
+            Pending::Closing(bracket, has_elements, level) => {
+                write_closing(output, has_elements, indent, level);
+                output.push(bracket);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The string a hash key is written as
+fn json_key(key: &HashKey) -> Rc<str> {
+    match key {
+        HashKey::String(key) => key.clone(),
+        other => Object::from(other.clone()).to_string().into(),
+    }
+}
+
+/// Goes before every element of an array or object
+fn write_separator(output: &mut String, index: usize, indent: usize, level: usize) {
+    if index > 0 {
+        output.push(',');
+    }
+    if indent > 0 {
+        output.push('\n');
+        output.push_str(&" ".repeat(indent * level));
+    }
+}
+
+/// Goes before the closing bracket, empty collections stay on one line
+fn write_closing(output: &mut String, has_elements: bool, indent: usize, level: usize) {
+    if has_elements && indent > 0 {
+        output.push('\n');
+        output.push_str(&" ".repeat(indent * level));
+    }
+}
+
+fn write_json_string(output: &mut String, value: &str) {
+    output.push('"');
+    for char_value in value.chars() {
+        match char_value {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\u{0}'..='\u{1f}' => output.push_str(&format!("\\u{:04x}", char_value as u32)),
+            other => output.push(other),
+        }
+    }
+    output.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::quote_string;
+    use crate::error::Error;
+    use crate::evaluator::tests::eval_input;
+
+    #[test]
+    fn eval_json_parse() {
+        for (input, expected) in [
+            (
+                r#"{"id": 7, "tags": ["a", "b"], "ok": true, "next": null}"#,
+                r#"{"id": 7, "next": null, "ok": true, "tags": ["a", "b"]}"#,
+            ),
+            (" [ ] ", "[]"),
+            ("{}", "{}"),
+            ("-12", "-12"),
+            (
+                "123456789012345678901234567890",
+                "123456789012345678901234567890",
+            ),
+            (
+                r#""tab\t quote\" slash\/ é 😀""#,
+                "\"tab\\t quote\\\" slash/ é 😀\"",
+            ),
+            (r#"{"a": 1, "a": 2}"#, r#"{"a": 2}"#),
+        ] {
+            let program = format!("[json_parse({})]", quote_string(input));
+            assert_eq!(
+                eval_input(&program).map(|value| value.to_string()),
+                Ok(format!("[{expected}]")),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            eval_input(r#"json_parse("{\"user\": {\"name\": \"ada\"}}")["user"]["name"]"#)
+                .map(|value| value.to_string()),
+            Ok("ada".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_json_parse_errors() {
+        for (input, offset, message) in [
+            ("", 0, "unexpected end of input"),
+            ("[1, 2", 5, "unexpected end of input"),
+            ("[1 2]", 3, "unexpected character '2'"),
+            (r#"{"a" 1}"#, 5, "unexpected character '1'"),
+            ("{a: 1}", 1, "unexpected character 'a'"),
+            ("[1,]", 3, "unexpected character ']'"),
+            (
+                r#"{"amount": 12.5}"#,
+                13,
+                "numbers with a fraction or exponent are not supported",
+            ),
+            (
+                "[1, -2E3]",
+                6,
+                "numbers with a fraction or exponent are not supported",
+            ),
+            ("01", 1, "unexpected character '1'"),
+            ("-", 1, "expected a digit"),
+            ("tru", 0, "expected true"),
+            ("\"abc", 4, "unterminated string"),
+            ("\"a\nb\"", 2, "control character in string"),
+            (r#""\x""#, 2, "invalid escape"),
+            (r#""\u12""#, 3, "expected 4 hex digits"),
+            (r#""\ud83d""#, 1, "unpaired surrogate"),
+            (r#""é" x"#, 5, "unexpected character 'x'"),
+        ] {
+            let program = format!("json_parse({})", quote_string(input));
+            assert_eq!(
+                eval_input(&program),
+                Err(Error::InvalidJson {
+                    message: message.to_string(),
+                    offset
+                }),
+                "{input}"
+            );
+        }
+
+        let nested = format!("json_parse(\"{}\")", "[".repeat(1000));
+        assert_eq!(
+            eval_input(&nested),
+            Err(Error::InvalidJson {
+                message: "nested deeper than 256 levels".to_string(),
+                offset: 256
+            })
+        );
+        assert_eq!(
+            eval_input("json_parse(1)"),
+            Err(Error::UnsupportedArgument {
+                function: "json_parse".to_string(),
+                got: "INTEGER".to_string()
+            })
+        );
+        // errors can be caught like any other
+        assert_eq!(
+            eval_input(r#"try { json_parse("[") } catch (error) { "bad payload" }"#)
+                .map(|value| value.to_string()),
+            Ok("bad payload".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_json_stringify() {
+        for (input, expected) in [
+            (
+                r#"json_stringify({"b": [1, json_parse("null")], "a": "x"})"#,
+                r#"{"a":"x","b":[1,null]}"#,
+            ),
+            ("json_stringify([])", "[]"),
+            ("json_stringify(pow(10, 20))", "100000000000000000000"),
+            ("json_stringify(\"a\\\"b\\n\u{1}\")", r#""a\"b\n\u0001""#),
+            (
+                "json_stringify({1: true, false: 2})",
+                r#"{"1":true,"false":2}"#,
+            ),
+            (
+                r#"json_stringify({"a": [1, 2], "b": {}, "c": []}, 2)"#,
+                "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {},\n  \"c\": []\n}",
+            ),
+        ] {
+            assert_eq!(
+                eval_input(input).map(|value| value.to_string()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+
+        let round_trip = r#"let value = {"list": [1, "two", {"three": false}], "none": json_parse("null")};
+            json_parse(json_stringify(value, 4)) == value"#;
+        assert_eq!(
+            eval_input(round_trip).map(|value| value.to_string()),
+            Ok("true".to_string())
+        );
+
+        assert_eq!(
+            eval_input("json_stringify([fn() { 1 }])"),
+            Err(Error::UnsupportedArgument {
+                function: "json_stringify".to_string(),
+                got: "FUNCTION".to_string()
+            })
+        );
+        for (input, key) in [
+            (r#"json_stringify({1: "a", "1": "b"})"#, "1"),
+            (r#"json_stringify([{"true": 1, true: 2}])"#, "true"),
+        ] {
+            assert_eq!(
+                eval_input(input),
+                Err(Error::DuplicateJsonKey(key.to_string())),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            eval_input("json_stringify(1, -1)"),
+            Err(Error::ArgumentOutOfRange {
+                function: "json_stringify".to_string(),
+                got: "-1".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use crate::error::Error;
+use crate::evaluator::builtin::{
+    expect_arguments, expect_arguments_with_default, unsupported_argument,
+};
+use crate::evaluator::limits::Limit;
+use crate::evaluator::{EvalResult, Evaluator, Overflow};
+use crate::object::{Builtin, Object};
+use num_bigint::BigInt;
+use num_traits::{One, Signed, Zero};
+
+/// Builtins for integer math, they overflow like the arithmetic operators do
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "abs",
+        function: abs,
+    },
+    Builtin {
+        name: "min",
+        function: min,
+    },
+    Builtin {
+        name: "max",
+        function: max,
+    },
+    Builtin {
+        name: "pow",
+        function: pow,
+    },
+    Builtin {
+        name: "sqrt",
+        function: sqrt,
+    },
+    Builtin {
+        name: "floor",
+        function: floor,
+    },
+];
+
+/// Most bits pow computes a result with, bigger results take too long to
+/// compute in a single step of a program so they are an overflow error
+const MAX_POW_BITS: u64 = 1 << 20;
+
+fn integer_argument(function: &str, argument: &Object) -> Result<BigInt, Error> {
+    argument
+        .to_big_integer()
+        .ok_or_else(|| unsupported_argument(function, argument))
+}
+
+fn out_of_range(function: &str, value: &BigInt) -> Error {
+    Error::ArgumentOutOfRange {
+        function: function.to_string(),
+        got: value.to_string(),
+    }
+}
+
+fn abs(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value] = expect_arguments(arguments)?;
+    let value = integer_argument("abs", &value)?;
+    Ok(evaluator
+        .overflow
+        .fit(value.abs(), || format!("abs({value})"))?)
+}
+
+/// The values to compare for min and max, either the arguments or the
+/// elements of an array passed on its own
+fn integer_values(function: &str, arguments: Vec<Object>) -> Result<Vec<BigInt>, Error> {
+    let values = match <[Object; 1]>::try_from(arguments) {
+        Ok([value]) => match &value {
+            Object::Array(elements) => elements.as_ref().clone(),
+            _ => vec![value],
+        },
+        Err(arguments) => arguments,
+    };
+    if values.is_empty() {
+        return Err(Error::ArgumentOutOfRange {
+            function: function.to_string(),
+            got: "no values".to_string(),
+        });
+    }
+    values
+        .iter()
+        .map(|value| integer_argument(function, value))
+        .collect()
+}
+
+/// Smallest of the arguments, or of the elements of an array
+fn min(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let values = integer_values("min", arguments)?;
+    Ok(Object::from(values.into_iter().min().unwrap_or_default()))
+}
+
+/// Largest of the arguments, or of the elements of an array
+fn max(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let values = integer_values("max", arguments)?;
+    Ok(Object::from(values.into_iter().max().unwrap_or_default()))
+}
+
+/// Raises base to a non-negative exponent. Results bigger than MAX_POW_BITS
+/// or the collection size limit are errors whatever the overflow option, ones
+/// that are sure to be are rejected before computing them
+fn pow(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [base, exponent] = expect_arguments(arguments)?;
+    let base = integer_argument("pow", &base)?;
+    let exponent = integer_argument("pow", &exponent)?;
+    let Ok(exponent) = u64::try_from(&exponent) else {
+        return Err(out_of_range("pow", &exponent).into());
+    };
+    let describe = || format!("pow({base}, {exponent})");
+
+    // wrapping only computes the lowest 64 bits, and -1, 0 and 1 stay small.
+    // The result has at least this many bits, since |base| >= 2^(bits - 1)
+    if evaluator.overflow != Overflow::Wrap && base.bits() > 1 {
+        let bits = (base.bits() - 1).saturating_mul(exponent).saturating_add(1);
+        if let Some(max) = evaluator.limits.max_collection_size {
+            if bits.div_ceil(8) > max as u64 {
+                return Err(Error::LimitExceeded(Limit::CollectionSize(max)).into());
+            }
+        }
+        if bits > MAX_POW_BITS {
+            return Err(Error::IntegerOverflow(describe()).into());
+        }
+    }
+
+    let result = match (evaluator.overflow, u32::try_from(exponent)) {
+        // wrapping only keeps the lowest 64 bits, whatever the exponent
+        (Overflow::Wrap, _) => base.modpow(&BigInt::from(exponent), &(BigInt::one() << 64)),
+        (_, Ok(exponent)) => base.pow(exponent),
+        // only -1, 0 and 1 stay in range for exponents this big
+        (_, Err(_)) if base.magnitude().is_one() || base.is_zero() => {
+            base.pow(if exponent % 2 == 1 { 1 } else { 2 })
+        }
+        (_, Err(_)) => return Err(Error::IntegerOverflow(describe()).into()),
+    };
+    if result.bits() > MAX_POW_BITS {
+        return Err(Error::IntegerOverflow(describe()).into());
+    }
+    Ok(evaluator.overflow.fit(result, describe)?)
+}
+
+/// Square root of a non-negative integer, rounded down
+fn sqrt(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value] = expect_arguments(arguments)?;
+    let value = integer_argument("sqrt", &value)?;
+    if value.is_negative() {
+        return Err(out_of_range("sqrt", &value).into());
+    }
+    Ok(Object::from(value.sqrt()))
+}
+
+/// Divides value by divisor (1 by default) rounding towards negative infinity,
+/// where `/` rounds towards zero. Every number is an integer, so with a single
+/// argument the value is returned as is
+fn floor(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value, divisor] = expect_arguments_with_default(arguments, Object::Integer(1))?;
+    let value = integer_argument("floor", &value)?;
+    let divisor = integer_argument("floor", &divisor)?;
+    if divisor.is_zero() {
+        return Err(Error::DivisionByZero.into());
+    }
+
+    let quotient = &value / &divisor;
+    let remainder = &value % &divisor;
+    let rounded_towards_zero =
+        !remainder.is_zero() && remainder.is_negative() != divisor.is_negative();
+    let quotient = if rounded_towards_zero {
+        quotient - 1
+    } else {
+        quotient
+    };
+    Ok(evaluator
+        .overflow
+        .fit(quotient, || format!("floor({value}, {divisor})"))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::evaluator::tests::{eval_input, eval_input_with_options};
+    use crate::evaluator::{Options, Overflow};
+    use crate::object::Object;
+
+    #[test]
+    fn eval_math_builtins() {
+        for (input, expected) in [
+            ("abs(-5)", 5),
+            ("abs(5)", 5),
+            ("min(3, -1, 2)", -1),
+            ("min([4, 2, 8])", 2),
+            ("max(3, -1, 2)", 3),
+            ("max(7)", 7),
+            ("pow(2, 10)", 1024),
+            ("pow(-3, 3)", -27),
+            ("pow(5, 0)", 1),
+            ("pow(-1, 4294967297)", -1),
+            // the largest power of two pow computes, MAX_POW_BITS bits
+            ("pow(2, 1048575) / pow(2, 1048574)", 2),
+            ("len(str(pow(2, 600000)))", 180618),
+            ("sqrt(16)", 4),
+            ("sqrt(17)", 4),
+            ("sqrt(0)", 0),
+            ("floor(7, 2)", 3),
+            ("floor(-7, 2)", -4),
+            ("floor(7, -2)", -4),
+            ("floor(-8, 2)", -4),
+            ("floor(5)", 5),
+            ("min(pow(2, 70), 5)", 5),
+            ("sqrt(pow(10, 36))", 1000000000000000000),
+            ("floor(pow(10, 20), -pow(10, 19))", -10),
+        ] {
+            assert_eq!(eval_input(input), Ok(Object::Integer(expected)), "{input}");
+        }
+
+        for (input, expected) in [
+            ("abs(-9223372036854775807 - 1)", "9223372036854775808"),
+            ("pow(2, 100)", "1267650600228229401496703205376"),
+            ("floor(-9223372036854775807 - 1, -1)", "9223372036854775808"),
+            ("max([1, pow(2, 64), -pow(2, 65)])", "18446744073709551616"),
+            (
+                "abs(-pow(3, 100))",
+                "515377520732011331036461129765621272702107522001",
+            ),
+        ] {
+            assert_eq!(
+                eval_input(input).map(|value| value.to_string()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn eval_math_builtin_errors() {
+        let options = Options {
+            overflow: Overflow::Error,
+            ..Options::default()
+        };
+        for (input, expected) in [
+            ("abs(-9223372036854775807 - 1)", "abs(-9223372036854775808)"),
+            ("pow(2, 63)", "pow(2, 63)"),
+            (
+                "floor(-9223372036854775807 - 1, -1)",
+                "floor(-9223372036854775808, -1)",
+            ),
+        ] {
+            assert_eq!(
+                eval_input_with_options(input, options.clone()),
+                Err(Error::IntegerOverflow(expected.to_string())),
+                "{input}"
+            );
+        }
+
+        for (input, expected) in [
+            (
+                "pow(2, 4294967296)",
+                Error::IntegerOverflow("pow(2, 4294967296)".to_string()),
+            ),
+            // too big to compute quickly
+            (
+                "pow(3, 200000000)",
+                Error::IntegerOverflow("pow(3, 200000000)".to_string()),
+            ),
+            // one bit more than MAX_POW_BITS
+            (
+                "pow(2, 1048576)",
+                Error::IntegerOverflow("pow(2, 1048576)".to_string()),
+            ),
+            // under the estimate, but over once computed
+            (
+                "pow(3, 700000)",
+                Error::IntegerOverflow("pow(3, 700000)".to_string()),
+            ),
+            (
+                "pow(2, -1)",
+                Error::ArgumentOutOfRange {
+                    function: "pow".to_string(),
+                    got: "-1".to_string(),
+                },
+            ),
+            (
+                "sqrt(-4)",
+                Error::ArgumentOutOfRange {
+                    function: "sqrt".to_string(),
+                    got: "-4".to_string(),
+                },
+            ),
+            (
+                "min([])",
+                Error::ArgumentOutOfRange {
+                    function: "min".to_string(),
+                    got: "no values".to_string(),
+                },
+            ),
+            (
+                r#"max(1, "2")"#,
+                Error::UnsupportedArgument {
+                    function: "max".to_string(),
+                    got: "STRING".to_string(),
+                },
+            ),
+            ("floor(1, 0)", Error::DivisionByZero),
+        ] {
+            assert_eq!(eval_input(input), Err(expected), "{input}");
+        }
+    }
+}
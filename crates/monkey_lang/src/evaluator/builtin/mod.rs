@@ -0,0 +1,242 @@
+mod array;
+mod io;
+mod json;
+mod math;
+mod string;
+
+use crate::error::Error;
+use crate::evaluator::capabilities::Capabilities;
+use crate::evaluator::limits::Limit;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::{Builtin, Object};
+use std::rc::Rc;
+
+/// Builtins installed in the root scope of every evaluator
+const BUILTINS: &[&[Builtin]] = &[
+    CORE_BUILTINS,
+    array::BUILTINS,
+    string::BUILTINS,
+    math::BUILTINS,
+    json::BUILTINS,
+];
+
+/// Builtins for the root scope of an evaluator, including the ones doing
+/// I/O the host granted the capabilities for
+pub(crate) fn builtins(capabilities: &Capabilities) -> impl Iterator<Item = Builtin> + '_ {
+    BUILTINS
+        .iter()
+        .copied()
+        .flatten()
+        .copied()
+        .chain(io::granted(capabilities))
+}
+
+const CORE_BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "len",
+        function: len,
+    },
+    Builtin {
+        name: "push",
+        function: push,
+    },
+    Builtin {
+        name: "str",
+        function: str,
+    },
+    Builtin {
+        name: "range",
+        function: range,
+    },
+    Builtin {
+        name: "zip",
+        function: zip,
+    },
+];
+
+/// Returns the arguments as a fixed size array, or an arity error
+pub(crate) fn expect_arguments<const N: usize>(
+    arguments: Vec<Object>,
+) -> Result<[Object; N], Error> {
+    let got = arguments.len();
+    arguments
+        .try_into()
+        .map_err(|_| Error::WrongNumberOfArguments { expected: N, got })
+}
+
+/// Like expect_arguments, but the last argument can be left out and
+/// defaults to the given value
+pub(crate) fn expect_arguments_with_default<const N: usize>(
+    mut arguments: Vec<Object>,
+    default: Object,
+) -> Result<[Object; N], Error> {
+    if arguments.len() + 1 == N {
+        arguments.push(default);
+    }
+    expect_arguments(arguments)
+}
+
+pub(crate) fn unsupported_argument(function: &str, argument: &Object) -> Error {
+    Error::UnsupportedArgument {
+        function: function.to_string(),
+        got: argument.type_name().to_string(),
+    }
+}
+
+/// Number of characters in a string, elements in an array or pairs in a hash
+fn len(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value] = expect_arguments(arguments)?;
+    let length = match &value {
+        Object::String(value) => value.chars().count(),
+        Object::Array(elements) => elements.len(),
+        Object::Hash(pairs) => pairs.len(),
+        other => return Err(unsupported_argument("len", other).into()),
+    };
+    Ok(Object::Integer(length as i64))
+}
+
+/// Returns a copy of the array with the value added to the end
+fn push(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [mut array, value] = expect_arguments(arguments)?;
+    match &mut array {
+        Object::Array(elements) => {
+            evaluator.check_depth(&value, 1)?;
+            Rc::make_mut(elements).push(value);
+            Ok(array)
+        }
+        other => Err(unsupported_argument("push", other).into()),
+    }
+}
+
+/// Integers from start up to but not including end, counting by step. A
+/// negative step counts down and a step of 0 gives an empty array. Every
+/// element counts as a step, so a long range can still be stopped
+fn range(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [start, end, step] = expect_arguments_with_default(arguments, Object::Integer(1))?;
+    let integer = |value: &Object| match value {
+        Object::Integer(value) => Ok(*value),
+        other => Err(unsupported_argument("range", other)),
+    };
+    let (start, end, step) = (integer(&start)?, integer(&end)?, integer(&step)?);
+
+    let mut elements = Vec::new();
+    let mut value = start;
+    while (step > 0 && value < end) || (step < 0 && value > end) {
+        evaluator.count_step()?;
+        if let Some(max) = evaluator.limits.max_collection_size {
+            if elements.len() >= max {
+                return Err(Error::LimitExceeded(Limit::CollectionSize(max)).into());
+            }
+        }
+        elements.push(Object::Integer(value));
+        // past the end of any range once it doesn't fit in an i64
+        let Some(next) = value.checked_add(step) else {
+            break;
+        };
+        value = next;
+    }
+    Ok(Object::Array(Rc::new(elements)))
+}
+
+/// Pairs up the elements of left and right, stopping at the end of the
+/// shorter one. Both go through their elements the same way a for loop does
+fn zip(evaluator: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [left, right] = expect_arguments(arguments)?;
+    let mut pairs = Vec::new();
+    for (left, right) in left.elements()?.into_iter().zip(right.elements()?) {
+        evaluator.check_depth(&left, 2)?;
+        evaluator.check_depth(&right, 2)?;
+        pairs.push(Object::Array(Rc::new(vec![left, right])));
+    }
+    Ok(Object::Array(Rc::new(pairs)))
+}
+
+/// Converts any value to the string it displays as
+fn str(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value] = expect_arguments(arguments)?;
+    Ok(Object::String(value.to_string().into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::evaluator::tests::eval_input;
+    use crate::object::Object;
+
+    #[test]
+    fn eval_builtins() {
+        assert_eq!(eval_input(r#"len("héllo")"#), Ok(Object::Integer(5)));
+        assert_eq!(eval_input("len([1, 2, 3])"), Ok(Object::Integer(3)));
+        assert_eq!(eval_input(r#"len({"a": 1})"#), Ok(Object::Integer(1)));
+        assert_eq!(
+            eval_input("let a = [1]; let b = push(a, 2); [a, b]").map(|value| value.to_string()),
+            Ok("[[1], [1, 2]]".to_string())
+        );
+        assert_eq!(
+            eval_input(r#"str(1) + str([true, "a"])"#),
+            Ok(Object::String(r#"1[true, "a"]"#.into()))
+        );
+        assert_eq!(
+            eval_input("len").map(|value| value.to_string()),
+            Ok("builtin len".to_string())
+        );
+        for (input, expected) in [
+            ("range(0, 4)", "[0, 1, 2, 3]"),
+            ("range(3, 0, -1)", "[3, 2, 1]"),
+            (
+                "range(9223372036854775806, 9223372036854775807, 5)",
+                "[9223372036854775806]",
+            ),
+            (r#"zip("ab", [1, 2, 3])"#, r#"[["a", 1], ["b", 2]]"#),
+            (r#"zip({"a": 1}, [])"#, "[]"),
+        ] {
+            assert_eq!(
+                eval_input(input).map(|value| value.to_string()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+        // builtins can be shadowed like any other binding
+        assert_eq!(eval_input("let len = 1; len"), Ok(Object::Integer(1)));
+    }
+
+    #[test]
+    fn eval_builtin_errors() {
+        assert_eq!(
+            eval_input("len(1)"),
+            Err(Error::UnsupportedArgument {
+                function: "len".to_string(),
+                got: "INTEGER".to_string()
+            })
+        );
+        assert_eq!(
+            eval_input("len([], [])"),
+            Err(Error::WrongNumberOfArguments {
+                expected: 1,
+                got: 2
+            })
+        );
+        assert_eq!(
+            eval_input("push(1, 2)"),
+            Err(Error::UnsupportedArgument {
+                function: "push".to_string(),
+                got: "INTEGER".to_string()
+            })
+        );
+        assert_eq!(
+            eval_input("range(0, pow(2, 64))"),
+            Err(Error::UnsupportedArgument {
+                function: "range".to_string(),
+                got: "INTEGER".to_string()
+            })
+        );
+        assert_eq!(
+            eval_input("zip([], 1)"),
+            Err(Error::NotIterable("INTEGER".to_string()))
+        );
+        assert_eq!(
+            eval_input("len(value: [])"),
+            Err(Error::UnknownParameter("value".to_string()))
+        );
+    }
+}
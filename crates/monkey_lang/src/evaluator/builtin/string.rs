@@ -0,0 +1,258 @@
+use crate::error::Error;
+use crate::evaluator::builtin::{
+    expect_arguments, expect_arguments_with_default, unsupported_argument,
+};
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::{Builtin, Object};
+use std::rc::Rc;
+
+/// Builtins for working with strings, positions and lengths are counted in
+/// characters rather than bytes, like len and indexing
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "split",
+        function: split,
+    },
+    Builtin {
+        name: "join",
+        function: join,
+    },
+    Builtin {
+        name: "trim",
+        function: trim,
+    },
+    Builtin {
+        name: "upper",
+        function: upper,
+    },
+    Builtin {
+        name: "lower",
+        function: lower,
+    },
+    Builtin {
+        name: "replace",
+        function: replace,
+    },
+    Builtin {
+        name: "contains",
+        function: contains,
+    },
+    Builtin {
+        name: "starts_with",
+        function: starts_with,
+    },
+    Builtin {
+        name: "substr",
+        function: substr,
+    },
+];
+
+fn string_argument<'a>(function: &str, argument: &'a Object) -> Result<&'a str, Error> {
+    match argument {
+        Object::String(value) => Ok(value),
+        other => Err(unsupported_argument(function, other)),
+    }
+}
+
+fn string_object(value: impl Into<Rc<str>>) -> Object {
+    Object::String(value.into())
+}
+
+/// Splits the string on every occurrence of the separator, an empty
+/// separator splits it into characters
+fn split(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value, separator] = expect_arguments(arguments)?;
+    let value = string_argument("split", &value)?;
+    let separator = string_argument("split", &separator)?;
+
+    let parts: Vec<Object> = if separator.is_empty() {
+        value
+            .chars()
+            .map(|char_value| string_object(char_value.to_string()))
+            .collect()
+    } else {
+        value.split(separator).map(string_object).collect()
+    };
+    Ok(Object::Array(Rc::new(parts)))
+}
+
+/// Joins the elements of an array into a string, converting them like str
+/// and putting the separator (empty by default) between them
+fn join(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [items, separator] = expect_arguments_with_default(arguments, string_object(""))?;
+    let separator = string_argument("join", &separator)?;
+    match &items {
+        Object::Array(elements) => Ok(string_object(
+            elements
+                .iter()
+                .map(|element| element.to_string())
+                .collect::<Vec<String>>()
+                .join(separator),
+        )),
+        other => Err(unsupported_argument("join", other).into()),
+    }
+}
+
+/// Removes whitespace from both ends of the string
+fn trim(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value] = expect_arguments(arguments)?;
+    Ok(string_object(string_argument("trim", &value)?.trim()))
+}
+
+fn upper(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value] = expect_arguments(arguments)?;
+    Ok(string_object(
+        string_argument("upper", &value)?.to_uppercase(),
+    ))
+}
+
+fn lower(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value] = expect_arguments(arguments)?;
+    Ok(string_object(
+        string_argument("lower", &value)?.to_lowercase(),
+    ))
+}
+
+/// Replaces every occurrence of from in the string with to
+fn replace(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value, from, to] = expect_arguments(arguments)?;
+    let value = string_argument("replace", &value)?;
+    let from = string_argument("replace", &from)?;
+    let to = string_argument("replace", &to)?;
+    Ok(string_object(value.replace(from, to)))
+}
+
+fn contains(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value, part] = expect_arguments(arguments)?;
+    let value = string_argument("contains", &value)?;
+    let part = string_argument("contains", &part)?;
+    Ok(Object::Boolean(value.contains(part)))
+}
+
+fn starts_with(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value, prefix] = expect_arguments(arguments)?;
+    let value = string_argument("starts_with", &value)?;
+    let prefix = string_argument("starts_with", &prefix)?;
+    Ok(Object::Boolean(value.starts_with(prefix)))
+}
+
+/// Up to length characters of the string from start onwards, the rest of
+/// the string if length is left out. Start can be at most the length of
+/// the string, a negative length takes nothing
+fn substr(_: &mut Evaluator, arguments: Vec<Object>) -> EvalResult {
+    let [value, start, length] = expect_arguments_with_default(arguments, Object::Null)?;
+    let value = string_argument("substr", &value)?;
+    let char_count = value.chars().count();
+
+    let out_of_range = |start: &Object| Error::ArgumentOutOfRange {
+        function: "substr".to_string(),
+        got: start.to_string(),
+    };
+    let start = match &start {
+        Object::Integer(index) => usize::try_from(*index)
+            .ok()
+            .filter(|index| *index <= char_count)
+            .ok_or_else(|| out_of_range(&start))?,
+        Object::BigInteger(_) => return Err(out_of_range(&start).into()),
+        other => return Err(unsupported_argument("substr", other).into()),
+    };
+    let length = match length {
+        Object::Integer(length) => usize::try_from(length).unwrap_or(0),
+        Object::Null => char_count,
+        other => return Err(unsupported_argument("substr", &other).into()),
+    };
+
+    Ok(string_object(
+        value.chars().skip(start).take(length).collect::<String>(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::evaluator::tests::eval_input;
+
+    #[test]
+    fn eval_string_builtins() {
+        for (input, expected) in [
+            (r#"split("a,b,,c", ",")"#, r#"["a", "b", "", "c"]"#),
+            (r#"split("héj", "")"#, r#"["h", "é", "j"]"#),
+            (r#"split("", ",")"#, r#"[""]"#),
+            (r#"join([1, "a", true], ", ")"#, r#""1, a, true""#),
+            ("join([])", r#""""#),
+            (r#"join(split("a b c", " "), "-")"#, r#""a-b-c""#),
+            (r#"trim("  a b \n")"#, r#""a b""#),
+            (r#"upper("straße")"#, r#""STRASSE""#),
+            (r#"lower("ÀB")"#, r#""àb""#),
+            (r#"replace("a-b-c", "-", "+")"#, r#""a+b+c""#),
+            (r#"contains("hello", "ell")"#, "true"),
+            (r#"contains("hello", "le")"#, "false"),
+            (r#"starts_with("hello", "he")"#, "true"),
+            (r#"starts_with("hello", "lo")"#, "false"),
+            (r#"substr("héllo", 1, 3)"#, r#""éll""#),
+            (r#"substr("héllo", 2)"#, r#""llo""#),
+            (r#"substr("héllo", 3, 10)"#, r#""lo""#),
+            (r#"substr("héllo", 5)"#, r#""""#),
+            (r#"substr("héllo", 1, -1)"#, r#""""#),
+        ] {
+            assert_eq!(
+                eval_input(&format!("[{input}]")).map(|value| value.to_string()),
+                Ok(format!("[{expected}]")),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn eval_string_builtin_errors() {
+        for (input, function, got) in [
+            (r#"split("a", 1)"#, "split", "INTEGER"),
+            (r#"join("abc")"#, "join", "STRING"),
+            (r#"join([], 1)"#, "join", "INTEGER"),
+            ("trim([])", "trim", "ARRAY"),
+            ("upper(1)", "upper", "INTEGER"),
+            ("lower(true)", "lower", "BOOLEAN"),
+            (r#"replace("a", "a", 1)"#, "replace", "INTEGER"),
+            (r#"contains(["a"], "a")"#, "contains", "ARRAY"),
+            (r#"starts_with("a", {})"#, "starts_with", "HASH"),
+            (r#"substr("a", "0")"#, "substr", "STRING"),
+        ] {
+            assert_eq!(
+                eval_input(input),
+                Err(Error::UnsupportedArgument {
+                    function: function.to_string(),
+                    got: got.to_string()
+                }),
+                "{input}"
+            );
+        }
+        for (input, got) in [
+            (r#"substr("abc", 4)"#, "4"),
+            (r#"substr("abc", -1)"#, "-1"),
+            (r#"substr("abc", pow(2, 64))"#, "18446744073709551616"),
+        ] {
+            assert_eq!(
+                eval_input(input),
+                Err(Error::ArgumentOutOfRange {
+                    function: "substr".to_string(),
+                    got: got.to_string()
+                }),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            eval_input(r#"replace("a", "a")"#),
+            Err(Error::WrongNumberOfArguments {
+                expected: 3,
+                got: 2
+            })
+        );
+        assert_eq!(
+            eval_input("join()"),
+            Err(Error::WrongNumberOfArguments {
+                expected: 2,
+                got: 0
+            })
+        );
+    }
+}
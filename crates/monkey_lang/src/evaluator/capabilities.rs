@@ -0,0 +1,93 @@
+use crate::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Access to the world outside the evaluator the host grants to scripts,
+/// nothing by default. The builtins that need a capability are only installed
+/// when it's granted, by allowing at least one path or variable
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    /// Files and directories read_file and list_dir can read, along with
+    /// everything under them
+    pub read: Vec<PathBuf>,
+    /// Files and directories write_file can write to, along with everything
+    /// under them
+    pub write: Vec<PathBuf>,
+    /// Names of the environment variables env_var can read
+    pub env_vars: Vec<String>,
+    /// Files and directories import can load modules from, along with
+    /// everything under them. The file given to eval_file is always run, only
+    /// what it imports is checked
+    pub modules: Vec<PathBuf>,
+}
+
+impl Capabilities {
+    /// The path resolved to where it really points, if read access was
+    /// granted for it
+    pub(crate) fn readable_path(&self, path: &str) -> Result<PathBuf, Error> {
+        allowed_path(&self.read, Path::new(path), path)
+    }
+
+    /// The path resolved to where it really points, if write access was
+    /// granted for it
+    pub(crate) fn writable_path(&self, path: &str) -> Result<PathBuf, Error> {
+        allowed_path(&self.write, Path::new(path), path)
+    }
+
+    /// The path resolved to where it really points, if modules can be
+    /// imported from it. display_path is the path as it was written
+    pub(crate) fn importable_path(
+        &self,
+        path: &Path,
+        display_path: &str,
+    ) -> Result<PathBuf, Error> {
+        allowed_path(&self.modules, path, display_path)
+    }
+
+    pub(crate) fn check_env_var(&self, name: &str) -> Result<(), Error> {
+        if self.env_vars.iter().any(|allowed| allowed == name) {
+            Ok(())
+        } else {
+            Err(Error::PermissionDenied(name.to_string()))
+        }
+    }
+}
+
+/// Paths are compared once symlinks and `..` are resolved, so neither can be
+/// used to get out of the allowed paths
+fn allowed_path(allowed: &[PathBuf], path: &Path, display_path: &str) -> Result<PathBuf, Error> {
+    let denied = || Error::PermissionDenied(display_path.to_string());
+    let resolved = resolve(path).ok_or_else(denied)?;
+    let is_allowed = allowed
+        .iter()
+        .filter_map(|allowed| allowed.canonicalize().ok())
+        .any(|allowed| resolved.starts_with(allowed));
+    if is_allowed {
+        Ok(resolved)
+    } else {
+        Err(denied())
+    }
+}
+
+/// Canonicalizes the path, which has to exist apart from its last components
+/// so files can be created. The check happens before anything is accessed,
+/// so a missing file outside the allowed paths is denied rather than not found
+fn resolve(path: &Path) -> Option<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return Some(
+                missing
+                    .iter()
+                    .rev()
+                    .fold(canonical, |path, name| path.join(name)),
+            );
+        }
+        // a missing .. can't be resolved without knowing what it leaves
+        missing.push(existing.file_name()?);
+        existing = match existing.parent()? {
+            parent if parent.as_os_str().is_empty() => Path::new("."),
+            parent => parent,
+        };
+    }
+}
@@ -0,0 +1,234 @@
+use crate::evaluator::environment::{Env, Environment};
+use crate::evaluator::Evaluator;
+use crate::object::Object;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::{Rc, Weak};
+
+/// Fewest captured scopes tracked before the collector does any work
+const MIN_THRESHOLD: usize = 256;
+
+/// Frees the reference cycles closures make with the scopes they capture.
+/// A function bound in the scope it was defined in, e.g. a recursive
+/// `let f = fn() { f() }` inside another function, keeps that scope alive
+/// and is kept alive by it, so reference counting alone never frees them.
+/// Every scope captured by a closure is tracked, a collection clears the
+/// ones that can't be reached from the evaluator's globals
+#[derive(Debug, Default)]
+pub(crate) struct Collector {
+    /// Scopes captured by a closure. The weak references keep the memory of
+    /// freed scopes allocated, so dead ones are pruned as more are added
+    scopes: Vec<Weak<RefCell<Environment>>>,
+    /// Scopes tracked since the last collection
+    tracked: usize,
+    /// Scopes still alive after the last collection or pruning
+    live: usize,
+}
+
+impl Collector {
+    /// Starts tracking a scope a closure was created in
+    pub(crate) fn track(&mut self, env: &Env) {
+        if !env.borrow_mut().capture() {
+            return;
+        }
+        self.scopes.push(Rc::downgrade(env));
+        self.tracked += 1;
+
+        if self.scopes.len() >= 2 * self.live.max(MIN_THRESHOLD) {
+            self.scopes.retain(|scope| scope.strong_count() > 0);
+            self.live = self.scopes.len();
+        }
+    }
+
+    /// Collections go through everything reachable, so they only happen
+    /// once enough scopes were captured since the last one
+    pub(crate) fn should_collect(&self) -> bool {
+        self.tracked >= self.live.max(MIN_THRESHOLD)
+    }
+
+    /// Clears every tracked scope that isn't reachable from the roots
+    fn collect(&mut self, roots: Vec<Env>, values: Vec<Object>) {
+        let reachable = mark(roots, values);
+        for scope in &self.scopes {
+            if let Some(scope) = scope.upgrade() {
+                if !reachable.contains(&Rc::as_ptr(&scope)) {
+                    clear(&scope);
+                }
+            }
+        }
+
+        self.scopes.retain(|scope| scope.strong_count() > 0);
+        self.live = self.scopes.len();
+        self.tracked = 0;
+    }
+
+    /// Clears every tracked scope, whether it's reachable or not
+    fn clear_all(&mut self) {
+        for scope in self.scopes.drain(..) {
+            if let Some(scope) = scope.upgrade() {
+                clear(&scope);
+            }
+        }
+    }
+}
+
+/// Drops the bindings and enclosing scope, the contents are dropped after the
+/// scope is released in case dropping them reaches back to it
+fn clear(scope: &Env) {
+    let contents = std::mem::take(&mut *scope.borrow_mut());
+    drop(contents);
+}
+
+/// Finds every scope reachable from the roots, through enclosing scopes and
+/// the values bound in them
+fn mark(mut scopes: Vec<Env>, mut values: Vec<Object>) -> HashSet<*const RefCell<Environment>> {
+    let mut reachable = HashSet::new();
+    // collections are shared, each one only has to be gone through once
+    let mut collections = HashSet::new();
+
+    loop {
+        if let Some(scope) = scopes.pop() {
+            if reachable.insert(Rc::as_ptr(&scope)) {
+                let scope = scope.borrow();
+                scopes.extend(scope.outer().cloned());
+                values.extend(scope.values().cloned());
+            }
+        } else if let Some(value) = values.pop() {
+            match &value {
+                Object::Function(function) => scopes.push(function.env.clone()),
+                Object::Array(elements)
+                    if collections.insert(Rc::as_ptr(elements) as *const ()) =>
+                {
+                    values.extend(elements.iter().cloned())
+                }
+                Object::Hash(pairs) if collections.insert(Rc::as_ptr(pairs) as *const ()) => {
+                    values.extend(pairs.values().cloned())
+                }
+                _ => {}
+            }
+        } else {
+            return reachable;
+        }
+    }
+}
+
+impl Evaluator {
+    /// Frees closures and scopes that are only kept alive by cycles between
+    /// them. This happens on its own between runs, it only needs calling to
+    /// release memory right away
+    pub fn collect_garbage(&mut self) {
+        let roots = vec![self.root.clone(), self.env.clone()];
+        let values = self.modules.exports().cloned().collect();
+        self.collector.collect(roots, values);
+    }
+}
+
+impl Drop for Evaluator {
+    fn drop(&mut self) {
+        // everything goes away with the evaluator, including cycles
+        self.collector.clear_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::evaluator::collector::MIN_THRESHOLD;
+    use crate::evaluator::Evaluator;
+    use crate::lexer::Lexer;
+    use crate::object::Object;
+    use crate::parser::Parser;
+    use std::rc::Rc;
+
+    fn eval(evaluator: &mut Evaluator, input: &str) -> Object {
+        let program = Parser::new(Lexer::new(input.chars()))
+            .parse_program()
+            .unwrap();
+        evaluator.eval_program(&program).unwrap()
+    }
+
+    fn live_scopes(evaluator: &Evaluator) -> usize {
+        evaluator
+            .collector
+            .scopes
+            .iter()
+            .filter(|scope| scope.strong_count() > 0)
+            .count()
+    }
+
+    #[test]
+    fn repl_session_memory_stays_bounded() {
+        let mut evaluator = Evaluator::new();
+        let baseline = live_scopes(&evaluator);
+        // every call leaves a scope that refers to itself through g
+        for _ in 0..10 * MIN_THRESHOLD {
+            eval(
+                &mut evaluator,
+                "let count = fn(n) { let g = fn(n) { if (n > 0) { 1 + g(n - 1) } else { 0 } }; g(n) }; \
+                count(3);",
+            );
+        }
+        assert!(live_scopes(&evaluator) <= baseline + 2 * MIN_THRESHOLD);
+        assert!(evaluator.collector.scopes.len() <= baseline + 2 * MIN_THRESHOLD);
+
+        evaluator.collect_garbage();
+        // the global scope now also captured by count
+        assert_eq!(live_scopes(&evaluator), baseline + 1);
+    }
+
+    #[test]
+    fn reachable_closures_survive_collection() {
+        let mut evaluator = Evaluator::new();
+        eval(
+            &mut evaluator,
+            "let make = fn(x) { let g = fn() { [x, g] }; g }; let kept = make(1); make(2);",
+        );
+        evaluator.collect_garbage();
+        assert_eq!(
+            eval(&mut evaluator, "kept()[1]()[1]()[0]"),
+            Object::Integer(1)
+        );
+        assert_eq!(
+            eval(&mut evaluator, "len(map([1, 2], fn(x) { x }))"),
+            Object::Integer(2)
+        );
+    }
+
+    #[test]
+    fn returned_closures_only_outlive_a_collection_while_bound() {
+        let mut evaluator = Evaluator::new();
+        let kept = eval(
+            &mut evaluator,
+            "let make = fn(x) { let g = fn() { x }; g }; let kept = make(1); kept",
+        );
+        let unbound = eval(&mut evaluator, "make(2)");
+        evaluator.collect_garbage();
+
+        let mut call = |function: Object| {
+            evaluator.env.borrow_mut().set("returned", function);
+            let program = Parser::new(Lexer::new("returned()".chars()))
+                .parse_program()
+                .unwrap();
+            evaluator
+                .eval_program(&program)
+                .map_err(|error| error.error)
+        };
+        assert_eq!(call(kept), Ok(Object::Integer(1)));
+        assert_eq!(
+            call(unbound),
+            Err(Error::IdentifierNotFound("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn dropping_the_evaluator_frees_cycles() {
+        let mut evaluator = Evaluator::new();
+        let global = Rc::downgrade(&evaluator.env);
+        let root = Rc::downgrade(&evaluator.root);
+        eval(&mut evaluator, "let f = fn() { f() };");
+
+        drop(evaluator);
+        assert!(global.upgrade().is_none());
+        assert!(root.upgrade().is_none());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::object::Object;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Shared handle to an environment, closures keep one of these alive
+pub type Env = Rc<RefCell<Environment>>;
+
+/// Maps identifiers to their values, with an optional enclosing scope
+#[derive(Debug, Default)]
+pub struct Environment {
+    store: HashMap<String, Object>,
+    outer: Option<Env>,
+    /// Whether a closure was created in this scope
+    captured: bool,
+}
+
+impl Environment {
+    pub fn new() -> Env {
+        Rc::new(RefCell::new(Self::default()))
+    }
+
+    /// Creates a new scope that falls back to outer for lookups
+    pub(crate) fn new_enclosed(outer: Env) -> Env {
+        Rc::new(RefCell::new(Self {
+            outer: Some(outer),
+            ..Self::default()
+        }))
+    }
+
+    /// Looks up a name in this scope, then in the enclosing scopes
+    pub fn get(&self, name: &str) -> Option<Object> {
+        match self.store.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .outer
+                .as_ref()
+                .and_then(|outer| outer.borrow().get(name)),
+        }
+    }
+
+    /// Binds a name in this scope, shadowing any outer binding
+    pub fn set(&mut self, name: &str, value: Object) {
+        self.store.insert(name.to_string(), value);
+    }
+
+    /// Runs update on the binding in the nearest scope that defines name,
+    /// returns None if the name isn't bound anywhere. The outermost scope is
+    /// left out, it holds the builtins and prelude every program shares
+    pub(crate) fn update<T>(
+        &mut self,
+        name: &str,
+        update: impl FnOnce(&mut Object) -> T,
+    ) -> Option<T> {
+        let outer = self.outer.as_ref()?;
+        match self.store.get_mut(name) {
+            Some(value) => Some(update(value)),
+            None => outer.borrow_mut().update(name, update),
+        }
+    }
+
+    /// Marks the scope as captured by a closure, returns false if it already was
+    pub(crate) fn capture(&mut self) -> bool {
+        !std::mem::replace(&mut self.captured, true)
+    }
+
+    pub(crate) fn outer(&self) -> Option<&Env> {
+        self.outer.as_ref()
+    }
+
+    /// Values bound in this scope, not including enclosing scopes
+    pub(crate) fn values(&self) -> impl Iterator<Item = &Object> {
+        self.store.values()
+    }
+
+    /// Removes the values bound in this scope, used to drop closures without recursing
+    pub(crate) fn take_values(&mut self) -> impl Iterator<Item = Object> + '_ {
+        self.store.drain().map(|(_, value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluator::environment::Environment;
+    use crate::object::Object;
+
+    #[test]
+    fn enclosed_environment_lookup() {
+        let outer = Environment::new();
+        outer.borrow_mut().set("a", Object::Integer(1));
+        outer.borrow_mut().set("b", Object::Integer(2));
+
+        let inner = Environment::new_enclosed(outer.clone());
+        inner.borrow_mut().set("b", Object::Integer(3));
+
+        assert_eq!(inner.borrow().get("a"), Some(Object::Integer(1)));
+        assert_eq!(inner.borrow().get("b"), Some(Object::Integer(3)));
+        assert_eq!(outer.borrow().get("b"), Some(Object::Integer(2)));
+        assert_eq!(inner.borrow().get("c"), None);
+    }
+
+    #[test]
+    fn update_nearest_binding() {
+        let root = Environment::new();
+        root.borrow_mut().set("b", Object::Integer(1));
+        let outer = Environment::new_enclosed(root.clone());
+        outer.borrow_mut().set("a", Object::Integer(1));
+        let inner = Environment::new_enclosed(outer.clone());
+
+        let updated = inner
+            .borrow_mut()
+            .update("a", |value| *value = Object::Integer(2));
+        assert_eq!(updated, Some(()));
+        assert_eq!(outer.borrow().get("a"), Some(Object::Integer(2)));
+
+        assert_eq!(inner.borrow_mut().update("b", |_| ()), None);
+        assert_eq!(inner.borrow_mut().update("c", |_| ()), None);
+    }
+}
@@ -0,0 +1,1355 @@
+use crate::ast::{Block, Expression, MatchArm, Parameter, StringPart};
+use crate::error::{Error, Frame, RuntimeError};
+use crate::evaluator::environment::{Env, Environment};
+use crate::evaluator::limits::Limit;
+use crate::evaluator::pattern::bind_pattern;
+use crate::evaluator::{EvalResult, Evaluator, Overflow, TailCall, Unwind};
+use crate::object::{Function, HashKey, Object};
+use crate::token::Span;
+use num_bigint::BigInt;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+impl Evaluator {
+    /// Errors get the span of the innermost expression they pass through
+    /// that has one
+    pub(crate) fn eval_expression(&mut self, expression: &Expression, env: &Env) -> EvalResult {
+        self.eval_expression_in(expression, env, false)
+    }
+
+    /// Evaluates the expression, if tail is set it's in tail position: a call
+    /// unwinds as a tail call instead of being made, and so does a call in tail
+    /// position of a branch of an if or match
+    pub(crate) fn eval_expression_in(
+        &mut self,
+        expression: &Expression,
+        env: &Env,
+        tail: bool,
+    ) -> EvalResult {
+        let result = self
+            .count_step()
+            .and_then(|_| self.eval_expression_kind(expression, env, tail))
+            .and_then(|value| {
+                self.check_collection_size(&value)?;
+                Ok(value)
+            });
+        match (result, expression.span()) {
+            (Err(Unwind::Error(mut error)), Some(span)) => {
+                error.span.get_or_insert(span);
+                Err(Unwind::Error(error))
+            }
+            (result, _) => result,
+        }
+    }
+
+    fn eval_expression_kind(
+        &mut self,
+        expression: &Expression,
+        env: &Env,
+        tail: bool,
+    ) -> EvalResult {
+        match expression {
+            Expression::Identifier(name, _) => self.eval_identifier(name, env),
+            Expression::IntegerLiteral(value) => Ok(Object::Integer(*value)),
+            Expression::BigIntegerLiteral(value) => Ok(Object::from(value.clone())),
+            Expression::StringLiteral(value) => Ok(Object::String(value.as_str().into())),
+            Expression::InterpolatedString(parts) => self.eval_interpolated_string(parts, env),
+            Expression::ArrayLiteral(elements) => self.eval_array_literal(elements, env),
+            Expression::HashLiteral(pairs) => self.eval_hash_literal(pairs, env),
+            Expression::Index { left, index, .. } => {
+                let left = self.eval_expression(left, env)?;
+                let index = self.eval_expression(index, env)?;
+                Ok(eval_index_expression(left, index)?)
+            }
+            Expression::Assign {
+                target,
+                operator,
+                value,
+                ..
+            } => self.eval_assign_expression(target, operator, value, env),
+            Expression::Boolean(value) => Ok(Object::Boolean(*value)),
+            Expression::Prefix {
+                operator, right, ..
+            } => {
+                let right = self.eval_expression(right, env)?;
+                Ok(eval_prefix_expression(operator, right, self.overflow)?)
+            }
+            Expression::Infix {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                let left = self.eval_expression(left, env)?;
+                let right = self.eval_expression(right, env)?;
+                Ok(eval_infix_expression(operator, left, right, self.overflow)?)
+            }
+            Expression::If {
+                condition,
+                consequence,
+                else_ifs,
+                alternative,
+            } => self.eval_if_expression(
+                condition,
+                consequence,
+                else_ifs,
+                alternative.as_ref(),
+                env,
+                tail,
+            ),
+            Expression::Match { value, arms, .. } => {
+                self.eval_match_expression(value, arms, env, tail)
+            }
+            Expression::Try { body, name, catch } => {
+                self.eval_try_expression(body, name, catch, env, tail)
+            }
+            Expression::FunctionLiteral { parameters, body } => {
+                Ok(self.eval_function_literal(parameters, body, None, env))
+            }
+            Expression::FunctionCall {
+                function,
+                arguments,
+                named_arguments,
+                span,
+            } => self.eval_call_expression(function, arguments, named_arguments, span, env, tail),
+        }
+    }
+
+    fn eval_array_literal(&mut self, elements: &[Expression], env: &Env) -> EvalResult {
+        let mut values = Vec::with_capacity(elements.len());
+        for element in elements {
+            let value = self.eval_expression(element, env)?;
+            self.check_depth(&value, 1)?;
+            values.push(value);
+        }
+        Ok(Object::Array(Rc::new(values)))
+    }
+
+    fn eval_interpolated_string(&mut self, parts: &[StringPart], env: &Env) -> EvalResult {
+        let mut result = String::new();
+        for part in parts {
+            match part {
+                StringPart::Text(text) => result.push_str(text),
+                StringPart::Expression(expression) => {
+                    let value = self.eval_expression(expression, env)?;
+                    result.push_str(&value.to_string());
+                }
+            }
+        }
+        Ok(Object::String(result.into()))
+    }
+
+    fn eval_hash_literal(&mut self, pairs: &[(Expression, Expression)], env: &Env) -> EvalResult {
+        let mut hash = BTreeMap::new();
+        for (key, value) in pairs {
+            let key = self.eval_expression(key, env)?.to_hash_key()?;
+            let value = self.eval_expression(value, env)?;
+            self.check_depth(&value, 1)?;
+            hash.insert(key, value);
+        }
+        Ok(Object::Hash(Rc::new(hash)))
+    }
+
+    /// Evaluates the function and then the arguments from left to right
+    fn eval_call_expression(
+        &mut self,
+        function: &Expression,
+        arguments: &[Box<Expression>],
+        named_arguments: &[(String, Expression)],
+        span: &Span,
+        env: &Env,
+        tail: bool,
+    ) -> EvalResult {
+        let function = self.eval_expression(function, env)?;
+        let mut argument_values = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            argument_values.push(self.eval_expression(argument, env)?);
+        }
+        let mut named_argument_values = Vec::with_capacity(named_arguments.len());
+        for (name, argument) in named_arguments {
+            named_argument_values.push((name.clone(), self.eval_expression(argument, env)?));
+        }
+        if tail {
+            return Err(Unwind::TailCall(Box::new(TailCall {
+                function,
+                arguments: argument_values,
+                named_arguments: named_argument_values,
+                span: *span,
+            })));
+        }
+
+        // errors coming out of a monkey function record the call in their stack
+        let name = match &function {
+            Object::Function(function) => Some(function.name()),
+            _ => None,
+        };
+        let result = self.apply_function(function, argument_values, named_argument_values);
+        match (result, name) {
+            (Err(Unwind::Error(mut error)), Some(name)) => {
+                error.stack.push(Frame {
+                    function: name,
+                    call_site: *span,
+                });
+                Err(Unwind::Error(error))
+            }
+            (result, _) => result,
+        }
+    }
+
+    fn eval_identifier(&mut self, name: &str, env: &Env) -> EvalResult {
+        env.borrow()
+            .get(name)
+            .ok_or_else(|| Error::IdentifierNotFound(name.to_string()).into())
+    }
+
+    /// Updates the binding the target refers to and evaluates to the new value.
+    /// Collections are copied on write, other bindings sharing them are left untouched
+    fn eval_assign_expression(
+        &mut self,
+        target: &Expression,
+        operator: &str,
+        value: &Expression,
+        env: &Env,
+    ) -> EvalResult {
+        // a[i][j] is stored as Index(Index(a, i), j), walk down to the identifier
+        let mut indexes = Vec::new();
+        let mut root = target;
+        while let Expression::Index { left, index, .. } = root {
+            indexes.push(index);
+            root = left;
+        }
+        let Expression::Identifier(name, _) = root else {
+            return Err(Error::InvalidAssignmentTarget(target.to_string()).into());
+        };
+
+        let mut index_values = Vec::with_capacity(indexes.len());
+        for index in indexes.into_iter().rev() {
+            index_values.push(self.eval_expression(index, env)?);
+        }
+        let value = self.eval_expression(value, env)?;
+        self.check_depth(&value, index_values.len())?;
+
+        let max_collection_size = self.limits.max_collection_size;
+        let overflow = self.overflow;
+        let result = env.borrow_mut().update(name, |binding| {
+            assign_index(
+                binding,
+                &index_values,
+                operator,
+                value,
+                max_collection_size,
+                overflow,
+            )
+        });
+        match result {
+            Some(result) => Ok(result?),
+            // the builtins and prelude are shared by every program and module
+            None if self.root.borrow().get(name).is_some() => {
+                Err(Error::AssignToBuiltin(name.to_string()).into())
+            }
+            None => Err(Error::IdentifierNotFound(name.to_string()).into()),
+        }
+    }
+
+    /// Runs the block of the first branch whose condition is truthy
+    fn eval_if_expression(
+        &mut self,
+        condition: &Expression,
+        consequence: &Block,
+        else_ifs: &[(Expression, Block)],
+        alternative: Option<&Block>,
+        env: &Env,
+        tail: bool,
+    ) -> EvalResult {
+        if self.eval_expression(condition, env)?.is_truthy() {
+            return self.eval_block_in(consequence, env, tail);
+        }
+
+        for (condition, consequence) in else_ifs {
+            if self.eval_expression(condition, env)?.is_truthy() {
+                return self.eval_block_in(consequence, env, tail);
+            }
+        }
+
+        if let Some(alternative) = alternative {
+            self.eval_block_in(alternative, env, tail)
+        } else {
+            Ok(Object::Null)
+        }
+    }
+
+    /// Runs the body, if it raises a runtime error the catch block runs in a new
+    /// scope with the error bound to name. Return, break and continue aren't
+    /// errors so they leave the try block as usual. Calls in the body are never
+    /// tail calls, they would leave the try block before raising their errors
+    fn eval_try_expression(
+        &mut self,
+        body: &Block,
+        name: &str,
+        catch: &Block,
+        env: &Env,
+        tail: bool,
+    ) -> EvalResult {
+        match self.with_tail_calls(false, |evaluator| evaluator.eval_block(body, env)) {
+            Err(Unwind::Error(error)) if error.error.is_catchable() => {
+                let catch_env = Environment::new_enclosed(env.clone());
+                let error = error_to_object(*error);
+                self.check_depth(&error, 0)?;
+                catch_env.borrow_mut().set(name, error);
+                self.eval_block_in(catch, &catch_env, tail)
+            }
+            result => result,
+        }
+    }
+
+    /// Runs the body of the first arm whose pattern matches, in a new scope
+    /// holding the bindings made by the pattern
+    fn eval_match_expression(
+        &mut self,
+        value: &Expression,
+        arms: &[MatchArm],
+        env: &Env,
+        tail: bool,
+    ) -> EvalResult {
+        let value = self.eval_expression(value, env)?;
+
+        for arm in arms {
+            let mut bindings = Vec::new();
+            if bind_pattern(&arm.pattern, &value, &mut bindings).is_ok() {
+                let arm_env = Environment::new_enclosed(env.clone());
+                for (name, value) in bindings {
+                    arm_env.borrow_mut().set(&name, value);
+                }
+                return self.eval_block_in(&arm.body, &arm_env, tail);
+            }
+        }
+
+        Err(Error::NoMatchingArm(value.to_string()).into())
+    }
+
+    /// Creates a function closing over env, functions bound with let are named
+    /// after their binding
+    pub(crate) fn eval_function_literal(
+        &mut self,
+        parameters: &[Parameter],
+        body: &Block,
+        name: Option<&str>,
+        env: &Env,
+    ) -> Object {
+        self.collector.track(env);
+        Object::Function(Rc::new(Function {
+            name: name.map(|name| name.to_string()),
+            parameters: parameters.to_vec(),
+            body: body.clone(),
+            env: env.clone(),
+        }))
+    }
+
+    /// Runs the function body in a new scope enclosed by the scope the
+    /// function was defined in. Tail calls out of the body are made here in a
+    /// loop, after the body's frame is gone
+    pub(crate) fn apply_function(
+        &mut self,
+        function: Object,
+        arguments: Vec<Object>,
+        named_arguments: Vec<(String, Object)>,
+    ) -> EvalResult {
+        let mut call = TailCall {
+            function,
+            arguments,
+            named_arguments,
+            span: Span::default(),
+        };
+        // only the latest tail call shows up in the stack of an error, the
+        // functions that made the ones before it have already returned
+        let mut tail_call = None;
+
+        loop {
+            let result = self.apply_function_once(call);
+            match (result, tail_call.take()) {
+                (Err(Unwind::TailCall(next)), _) => {
+                    tail_call = match &next.function {
+                        Object::Function(function) => Some((function.clone(), next.span)),
+                        _ => None,
+                    };
+                    call = *next;
+                }
+                (Err(Unwind::Error(mut error)), Some((function, call_site))) => {
+                    error.stack.push(Frame {
+                        function: function.name(),
+                        call_site,
+                    });
+                    return Err(Unwind::Error(error));
+                }
+                (result, _) => return result,
+            }
+        }
+    }
+
+    /// Runs a single call, a tail call out of the body is returned to be made
+    /// by apply_function
+    fn apply_function_once(&mut self, call: TailCall) -> EvalResult {
+        let function = match &call.function {
+            Object::Function(function) => function.clone(),
+            Object::Builtin(builtin) => {
+                // builtins only take positional arguments
+                if let Some((name, _)) = call.named_arguments.into_iter().next() {
+                    return Err(Error::UnknownParameter(name).into());
+                }
+                return (builtin.function)(self, call.arguments);
+            }
+            other => return Err(Error::NotAFunction(other.type_name().to_string()).into()),
+        };
+
+        self.enter_call()?;
+        let function_env = Environment::new_enclosed(function.env.clone());
+        let result = self.with_tail_calls(true, |evaluator| {
+            evaluator
+                .bind_parameters(
+                    &function.parameters,
+                    call.arguments,
+                    call.named_arguments,
+                    &function_env,
+                )
+                .and_then(|_| evaluator.eval_block_in(&function.body, &function_env, true))
+        });
+        self.exit_call();
+
+        match result {
+            Err(Unwind::Return(value)) => Ok(value),
+            // a break or continue can't leave the function for a loop around the call
+            Err(unwind @ (Unwind::Break | Unwind::Continue)) => {
+                Err(Unwind::Error(Box::new(unwind.into_error())))
+            }
+            result => result,
+        }
+    }
+
+    /// Binds the arguments of a call to the parameters of the function, positional
+    /// arguments fill the parameters in order and any left over go to the variadic
+    /// parameter. Defaults are evaluated in order in the function scope so they can
+    /// refer to the parameters before them
+    fn bind_parameters(
+        &mut self,
+        parameters: &[Parameter],
+        arguments: Vec<Object>,
+        named_arguments: Vec<(String, Object)>,
+        function_env: &Env,
+    ) -> Result<(), Unwind> {
+        let positional_count = parameters
+            .iter()
+            .filter(|parameter| !matches!(parameter, Parameter::Variadic(_)))
+            .count();
+        let has_variadic = positional_count != parameters.len();
+        if arguments.len() > positional_count && !has_variadic {
+            return Err(Error::WrongNumberOfArguments {
+                expected: positional_count,
+                got: arguments.len(),
+            }
+            .into());
+        }
+
+        let mut arguments = arguments.into_iter();
+        let mut values: Vec<Option<Object>> = parameters
+            .iter()
+            .map(|parameter| match parameter {
+                Parameter::Variadic(_) => {
+                    Some(Object::Array(Rc::new(arguments.by_ref().collect())))
+                }
+                _ => arguments.next(),
+            })
+            .collect();
+
+        for (name, argument) in named_arguments {
+            let position = parameters
+                .iter()
+                .position(|parameter| parameter.name() == name)
+                .ok_or_else(|| Error::UnknownParameter(name.clone()))?;
+            if let Parameter::Variadic(_) = parameters[position] {
+                return Err(Error::UnknownParameter(name).into());
+            }
+            if values[position].is_some() {
+                return Err(Error::DuplicateArgument(name).into());
+            }
+            values[position] = Some(argument);
+        }
+
+        for (parameter, value) in parameters.iter().zip(values) {
+            let value = match (parameter, value) {
+                (_, Some(value)) => value,
+                (Parameter::Default(_, default), None) => {
+                    self.eval_expression(default, function_env)?
+                }
+                (parameter, None) => {
+                    return Err(Error::MissingArgument(parameter.name().to_string()).into())
+                }
+            };
+            if let Parameter::Variadic(_) = parameter {
+                self.check_depth(&value, 0)?;
+            }
+            function_env.borrow_mut().set(parameter.name(), value);
+        }
+        Ok(())
+    }
+}
+
+/// Converts a caught error to a hash with the message, the thrown value (the
+/// message for errors raised by the interpreter) and the stack, innermost call first
+fn error_to_object(error: RuntimeError) -> Object {
+    let message = Object::String(error.error.to_string().into());
+    let value = match error.error {
+        Error::Thrown(value) => value,
+        _ => message.clone(),
+    };
+    let stack = error
+        .stack
+        .into_iter()
+        .map(|frame| {
+            hash_object([
+                ("function", Object::String(frame.function.into())),
+                ("line", Object::Integer(frame.call_site.start.line as i64)),
+                (
+                    "column",
+                    Object::Integer(frame.call_site.start.column as i64),
+                ),
+            ])
+        })
+        .collect();
+
+    hash_object([
+        ("message", message),
+        ("value", value),
+        ("stack", Object::Array(Rc::new(stack))),
+    ])
+}
+
+/// Builds a hash object with string keys
+pub(crate) fn hash_object<const N: usize>(pairs: [(&str, Object); N]) -> Object {
+    Object::Hash(Rc::new(
+        pairs
+            .into_iter()
+            .map(|(key, value)| (HashKey::String(key.into()), value))
+            .collect(),
+    ))
+}
+
+fn eval_prefix_expression(
+    operator: &str,
+    right: Object,
+    overflow: Overflow,
+) -> Result<Object, Error> {
+    match (operator, &right) {
+        ("!", right) => Ok(Object::Boolean(!right.is_truthy())),
+        ("-", Object::Integer(value)) => match value.checked_neg() {
+            Some(result) => Ok(Object::Integer(result)),
+            None => overflow.fit(-BigInt::from(*value), || format!("-({value})")),
+        },
+        ("-", Object::BigInteger(value)) => overflow.fit(-value.as_ref(), || format!("-({value})")),
+        (operator, right) => Err(Error::UnknownOperator(format!(
+            "{operator}{}",
+            right.type_name()
+        ))),
+    }
+}
+
+fn eval_infix_expression(
+    operator: &str,
+    left: Object,
+    right: Object,
+    overflow: Overflow,
+) -> Result<Object, Error> {
+    match (&left, &right) {
+        (Object::Integer(left), Object::Integer(right)) => {
+            match eval_integer_infix_expression(operator, *left, *right)? {
+                Some(result) => Ok(result),
+                None => eval_big_integer_infix_expression(
+                    operator,
+                    BigInt::from(*left),
+                    BigInt::from(*right),
+                    overflow,
+                ),
+            }
+        }
+        (Object::String(left), Object::String(right)) if operator == "+" => {
+            Ok(Object::String(format!("{left}{right}").into()))
+        }
+        (left, right) => {
+            match (left.to_big_integer(), right.to_big_integer()) {
+                (Some(left), Some(right)) => {
+                    eval_big_integer_infix_expression(operator, left, right, overflow)
+                }
+                _ => match operator {
+                    "==" => Ok(Object::Boolean(left == right)),
+                    "!=" => Ok(Object::Boolean(left != right)),
+                    _ if left.type_name() != right.type_name() => Err(Error::TypeMismatch(
+                        format!("{} {operator} {}", left.type_name(), right.type_name()),
+                    )),
+                    _ => Err(Error::UnknownOperator(format!(
+                        "{} {operator} {}",
+                        left.type_name(),
+                        right.type_name()
+                    ))),
+                },
+            }
+        }
+    }
+}
+
+/// Out of range array indexes and missing hash keys evaluate to null
+fn eval_index_expression(left: Object, index: Object) -> Result<Object, Error> {
+    match (&left, &index) {
+        (Object::Array(elements), Object::Integer(index)) => Ok(usize::try_from(*index)
+            .ok()
+            .and_then(|index| elements.get(index).cloned())
+            .unwrap_or(Object::Null)),
+        // strings are indexed by character, not by byte
+        (Object::String(value), Object::Integer(index)) => Ok(usize::try_from(*index)
+            .ok()
+            .and_then(|index| value.chars().nth(index))
+            .map_or(Object::Null, |char_value| {
+                Object::String(char_value.to_string().into())
+            })),
+        // too big to be in range
+        (Object::Array(_) | Object::String(_), Object::BigInteger(_)) => Ok(Object::Null),
+        (Object::Hash(pairs), index) => Ok(pairs
+            .get(&index.to_hash_key()?)
+            .cloned()
+            .unwrap_or(Object::Null)),
+        (left, index) => Err(Error::IndexNotSupported(format!(
+            "{}[{}]",
+            left.type_name(),
+            index.type_name()
+        ))),
+    }
+}
+
+/// Follows indexes down from binding and stores value at the end of the path,
+/// compound operators combine the value with what is currently stored there
+fn assign_index(
+    binding: &mut Object,
+    indexes: &[Object],
+    operator: &str,
+    value: Object,
+    max_collection_size: Option<usize>,
+    overflow: Overflow,
+) -> Result<Object, Error> {
+    let Some((index, rest)) = indexes.split_first() else {
+        let value = match operator.strip_suffix('=') {
+            Some("") | None => value,
+            Some(infix_operator) => {
+                eval_infix_expression(infix_operator, binding.clone(), value, overflow)?
+            }
+        };
+        *binding = value.clone();
+        return Ok(value);
+    };
+
+    match (binding, index) {
+        (Object::Array(elements), Object::Integer(index)) => {
+            let length = elements.len();
+            let element = usize::try_from(*index)
+                .ok()
+                .and_then(|index| Rc::make_mut(elements).get_mut(index))
+                .ok_or(Error::IndexOutOfBounds {
+                    index: *index,
+                    length,
+                })?;
+            assign_index(
+                element,
+                rest,
+                operator,
+                value,
+                max_collection_size,
+                overflow,
+            )
+        }
+        (Object::Hash(pairs), index) => {
+            let key = index.to_hash_key()?;
+            let pairs = Rc::make_mut(pairs);
+            match pairs.get_mut(&key) {
+                Some(element) => assign_index(
+                    element,
+                    rest,
+                    operator,
+                    value,
+                    max_collection_size,
+                    overflow,
+                ),
+                None if rest.is_empty() && operator == "=" => {
+                    // the only way an assignment can grow a collection
+                    if let Some(max) = max_collection_size.filter(|max| pairs.len() >= *max) {
+                        return Err(Error::LimitExceeded(Limit::CollectionSize(max)));
+                    }
+                    pairs.insert(key, value.clone());
+                    Ok(value)
+                }
+                None => Err(Error::KeyNotFound(index.to_string())),
+            }
+        }
+        (binding, index) => Err(Error::IndexNotSupported(format!(
+            "{}[{}]",
+            binding.type_name(),
+            index.type_name()
+        ))),
+    }
+}
+
+/// Division and modulo truncate towards zero, so the result of `%` takes
+/// the sign of the left operand. None when the result doesn't fit in an i64
+fn eval_integer_infix_expression(
+    operator: &str,
+    left: i64,
+    right: i64,
+) -> Result<Option<Object>, Error> {
+    let result = match operator {
+        "+" => left.checked_add(right).map(Object::Integer),
+        "-" => left.checked_sub(right).map(Object::Integer),
+        "*" => left.checked_mul(right).map(Object::Integer),
+        "/" if right == 0 => return Err(Error::DivisionByZero),
+        "/" => left.checked_div(right).map(Object::Integer),
+        "%" if right == 0 => return Err(Error::ModuloByZero),
+        // only i64::MIN % -1 overflows, and its remainder is 0 either way
+        "%" => Some(Object::Integer(left.wrapping_rem(right))),
+        "<" => Some(Object::Boolean(left < right)),
+        ">" => Some(Object::Boolean(left > right)),
+        "<=" => Some(Object::Boolean(left <= right)),
+        ">=" => Some(Object::Boolean(left >= right)),
+        "==" => Some(Object::Boolean(left == right)),
+        "!=" => Some(Object::Boolean(left != right)),
+        _ => {
+            return Err(Error::UnknownOperator(format!(
+                "INTEGER {operator} INTEGER"
+            )))
+        }
+    };
+    Ok(result)
+}
+
+/// Same as for i64, used when either operand or the result doesn't fit in one
+fn eval_big_integer_infix_expression(
+    operator: &str,
+    left: BigInt,
+    right: BigInt,
+    overflow: Overflow,
+) -> Result<Object, Error> {
+    let describe = || format!("{left} {operator} {right}");
+    let result = match operator {
+        "+" => overflow.fit(&left + &right, describe)?,
+        "-" => overflow.fit(&left - &right, describe)?,
+        "*" => overflow.fit(&left * &right, describe)?,
+        "/" if right == BigInt::ZERO => return Err(Error::DivisionByZero),
+        "/" => overflow.fit(&left / &right, describe)?,
+        "%" if right == BigInt::ZERO => return Err(Error::ModuloByZero),
+        "%" => Object::from(left % right),
+        "<" => Object::Boolean(left < right),
+        ">" => Object::Boolean(left > right),
+        "<=" => Object::Boolean(left <= right),
+        ">=" => Object::Boolean(left >= right),
+        "==" => Object::Boolean(left == right),
+        "!=" => Object::Boolean(left != right),
+        _ => {
+            return Err(Error::UnknownOperator(format!(
+                "INTEGER {operator} INTEGER"
+            )))
+        }
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::evaluator::tests::{eval_input, eval_input_with_options};
+    use crate::evaluator::{Options, Overflow};
+    use crate::object::Object;
+
+    #[test]
+    fn eval_integer_expressions() {
+        assert_eq!(eval_input("5"), Ok(Object::Integer(5)));
+        assert_eq!(eval_input("-10"), Ok(Object::Integer(-10)));
+        assert_eq!(eval_input("--10"), Ok(Object::Integer(10)));
+        assert_eq!(eval_input("5 + 5 + 5 + 5 - 10"), Ok(Object::Integer(10)));
+        assert_eq!(eval_input("2 * 2 * 2 * 2 * 2"), Ok(Object::Integer(32)));
+        assert_eq!(eval_input("-50 + 100 + -50"), Ok(Object::Integer(0)));
+        assert_eq!(eval_input("20 + 2 * -10"), Ok(Object::Integer(0)));
+        assert_eq!(eval_input("50 / 2 * 2 + 10"), Ok(Object::Integer(60)));
+        assert_eq!(
+            eval_input("(5 + 10 * 2 + 15 / 3) * 2 + -10"),
+            Ok(Object::Integer(50))
+        );
+        assert_eq!(eval_input("10 % 3"), Ok(Object::Integer(1)));
+        assert_eq!(eval_input("-10 % 3"), Ok(Object::Integer(-1)));
+        assert_eq!(eval_input("10 % -3"), Ok(Object::Integer(1)));
+        assert_eq!(eval_input("1 + 10 % 4 * 2"), Ok(Object::Integer(5)));
+    }
+
+    #[test]
+    fn eval_big_integers() {
+        for (input, expected) in [
+            ("9223372036854775807 + 1", "9223372036854775808"),
+            ("-(-9223372036854775807 - 1)", "9223372036854775808"),
+            ("4611686018427387904 * 4", "18446744073709551616"),
+            ("100000000000000000000 % 7", "2"),
+            ("100000000000000000000 / -100000000000000000000", "-1"),
+            (
+                "let f = fn(n) { if (n < 2) { 1 } else { n * f(n - 1) } }; f(25)",
+                "15511210043330985984000000",
+            ),
+            (
+                "[9223372036854775808 > 9223372036854775807, 9223372036854775807 + 1 == 9223372036854775808]",
+                "[true, true]",
+            ),
+            ("{9223372036854775808: 1}[9223372036854775807 + 1]", "1"),
+            ("[1][9223372036854775808]", "null"),
+            ("let x = 9223372036854775807; x += 1", "9223372036854775808"),
+        ] {
+            assert_eq!(
+                eval_input(input).map(|value| value.to_string()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+
+        // results that fit go back to being plain integers
+        assert_eq!(
+            eval_input("-9223372036854775808"),
+            Ok(Object::Integer(i64::MIN))
+        );
+        assert_eq!(
+            eval_input("99999999999999999999 - 99999999999999999998"),
+            Ok(Object::Integer(1))
+        );
+        assert_eq!(
+            eval_input("9223372036854775808 / 2"),
+            Ok(Object::Integer(4611686018427387904))
+        );
+    }
+
+    #[test]
+    fn eval_integer_overflow() {
+        let options = Options {
+            overflow: Overflow::Error,
+            ..Options::default()
+        };
+        for (input, expected) in [
+            ("9223372036854775807 + 1", "9223372036854775807 + 1"),
+            ("-9223372036854775807 - 2", "-9223372036854775807 - 2"),
+            ("4611686018427387904 * 2", "4611686018427387904 * 2"),
+            (
+                "(-9223372036854775807 - 1) / -1",
+                "-9223372036854775808 / -1",
+            ),
+            ("-(-9223372036854775807 - 1)", "-(-9223372036854775808)"),
+            (
+                "let x = 9223372036854775807; x += 1",
+                "9223372036854775807 + 1",
+            ),
+            ("9223372036854775808 + 0", "9223372036854775808 + 0"),
+        ] {
+            assert_eq!(
+                eval_input_with_options(input, options.clone()),
+                Err(Error::IntegerOverflow(expected.to_string())),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            eval_input_with_options("(-9223372036854775807 - 1) % -1", options),
+            Ok(Object::Integer(0))
+        );
+    }
+
+    #[test]
+    fn eval_division_by_zero() {
+        assert_eq!(eval_input("1 / 0"), Err(Error::DivisionByZero));
+        assert_eq!(eval_input("1 % 0"), Err(Error::ModuloByZero));
+        assert_eq!(eval_input("let a = 0; 5 % a; 10"), Err(Error::ModuloByZero));
+    }
+
+    #[test]
+    fn eval_boolean_expressions() {
+        assert_eq!(eval_input("true"), Ok(Object::Boolean(true)));
+        assert_eq!(eval_input("!true"), Ok(Object::Boolean(false)));
+        assert_eq!(eval_input("!!5"), Ok(Object::Boolean(true)));
+        assert_eq!(eval_input("1 < 2"), Ok(Object::Boolean(true)));
+        assert_eq!(eval_input("1 > 2"), Ok(Object::Boolean(false)));
+        assert_eq!(eval_input("1 <= 1"), Ok(Object::Boolean(true)));
+        assert_eq!(eval_input("2 <= 1"), Ok(Object::Boolean(false)));
+        assert_eq!(eval_input("1 >= 1"), Ok(Object::Boolean(true)));
+        assert_eq!(eval_input("1 >= 2"), Ok(Object::Boolean(false)));
+        assert_eq!(eval_input("1 == 1"), Ok(Object::Boolean(true)));
+        assert_eq!(eval_input("1 != 1"), Ok(Object::Boolean(false)));
+        assert_eq!(eval_input("true == true"), Ok(Object::Boolean(true)));
+        assert_eq!(eval_input("true != false"), Ok(Object::Boolean(true)));
+        assert_eq!(eval_input("(1 < 2) == true"), Ok(Object::Boolean(true)));
+        assert_eq!(eval_input("1 == true"), Ok(Object::Boolean(false)));
+    }
+
+    #[test]
+    fn eval_operator_errors() {
+        assert_eq!(
+            eval_input("5 + true;"),
+            Err(Error::TypeMismatch("INTEGER + BOOLEAN".to_string()))
+        );
+        assert_eq!(
+            eval_input("true <= 5;"),
+            Err(Error::TypeMismatch("BOOLEAN <= INTEGER".to_string()))
+        );
+        assert_eq!(
+            eval_input("-true"),
+            Err(Error::UnknownOperator("-BOOLEAN".to_string()))
+        );
+        assert_eq!(
+            eval_input("true % false;"),
+            Err(Error::UnknownOperator("BOOLEAN % BOOLEAN".to_string()))
+        );
+    }
+
+    #[test]
+    fn eval_collection_literals() {
+        assert_eq!(
+            eval_input(r#""hello" + " " + "world""#),
+            Ok(Object::String("hello world".into()))
+        );
+        assert_eq!(eval_input(r#""a" == "a""#), Ok(Object::Boolean(true)));
+        assert_eq!(
+            eval_input("[1, 2 * 2, 3 + 3]").map(|value| value.to_string()),
+            Ok("[1, 4, 6]".to_string())
+        );
+        assert_eq!(
+            eval_input(r#"let two = "two"; {"one": 10 - 9, two: 1 + 1, 3: "c", true: [1]}"#)
+                .map(|value| value.to_string()),
+            Ok(r#"{3: "c", true: [1], "one": 1, "two": 2}"#.to_string())
+        );
+        assert_eq!(
+            eval_input("{fn(x) { x }: 1}"),
+            Err(Error::UnusableHashKey("FUNCTION".to_string()))
+        );
+    }
+
+    #[test]
+    fn eval_interpolated_strings() {
+        assert_eq!(
+            eval_input(
+                r#"let name = "Ann"; let items = [1, "b"]; "hello ${name}, you have ${len(items)} items: ${items}""#
+            ),
+            Ok(Object::String(
+                r#"hello Ann, you have 2 items: [1, "b"]"#.into()
+            ))
+        );
+        assert_eq!(
+            eval_input(r#""${"nested ${1 + 1}"}!""#),
+            Ok(Object::String("nested 2!".into()))
+        );
+        assert_eq!(
+            eval_input(r#""\${not} $ {interpolated}""#),
+            Ok(Object::String("${not} $ {interpolated}".into()))
+        );
+    }
+
+    #[test]
+    fn eval_index_expressions() {
+        assert_eq!(eval_input("[1, 2, 3][0]"), Ok(Object::Integer(1)));
+        assert_eq!(
+            eval_input("let i = 1; [1, 2, 3][i + 1]"),
+            Ok(Object::Integer(3))
+        );
+        assert_eq!(eval_input("[1, 2, 3][3]"), Ok(Object::Null));
+        assert_eq!(eval_input("[1, 2, 3][-1]"), Ok(Object::Null));
+        assert_eq!(eval_input(r#"{"foo": 5}["foo"]"#), Ok(Object::Integer(5)));
+        assert_eq!(eval_input(r#"{"foo": 5}["bar"]"#), Ok(Object::Null));
+        assert_eq!(eval_input("{true: 5}[true]"), Ok(Object::Integer(5)));
+        assert_eq!(eval_input(r#""héllo"[1]"#), Ok(Object::String("é".into())));
+        assert_eq!(eval_input(r#""héllo"[5]"#), Ok(Object::Null));
+        assert_eq!(eval_input(r#""héllo"[-1]"#), Ok(Object::Null));
+        assert_eq!(
+            eval_input("1[0]"),
+            Err(Error::IndexNotSupported("INTEGER[INTEGER]".to_string()))
+        );
+    }
+
+    #[test]
+    fn eval_assign_expressions() {
+        assert_eq!(eval_input("let x = 1; x = 5; x"), Ok(Object::Integer(5)));
+        assert_eq!(eval_input("let x = 1; x = x + 1"), Ok(Object::Integer(2)));
+        assert_eq!(
+            eval_input("let x = 1; let y = 2; x = y = 3; x + y"),
+            Ok(Object::Integer(6))
+        );
+        assert_eq!(
+            eval_input("let x = 10; x += 5; x -= 3; x *= 2; x /= 4; x"),
+            Ok(Object::Integer(6))
+        );
+        assert_eq!(
+            eval_input(r#"let s = "a"; s += "b"; s"#),
+            Ok(Object::String("ab".into()))
+        );
+        assert_eq!(
+            eval_input(
+                "let counter = fn() { let count = 0; fn() { count += 1 } }; \
+                let next = counter(); next(); next(); next()"
+            ),
+            Ok(Object::Integer(3))
+        );
+        assert_eq!(
+            eval_input("let i = 0; let total = 0; while (i < 5) { i += 1; total += i; } total"),
+            Ok(Object::Integer(15))
+        );
+    }
+
+    #[test]
+    fn eval_index_assign_expressions() {
+        assert_eq!(
+            eval_input("let a = [1, 2, 3]; a[1] = 5; a").map(|value| value.to_string()),
+            Ok("[1, 5, 3]".to_string())
+        );
+        assert_eq!(
+            eval_input("let a = [[1, 2], [3, 4]]; a[1][0] *= 10; a").map(|value| value.to_string()),
+            Ok("[[1, 2], [30, 4]]".to_string())
+        );
+        assert_eq!(
+            eval_input(r#"let h = {"a": 1}; h["b"] = 2; h["a"] += 1; h"#)
+                .map(|value| value.to_string()),
+            Ok(r#"{"a": 2, "b": 2}"#.to_string())
+        );
+        // collections have value semantics, other bindings keep the old contents
+        assert_eq!(
+            eval_input("let a = [1]; let b = a; b[0] = 2; a[0] + b[0]"),
+            Ok(Object::Integer(3))
+        );
+    }
+
+    #[test]
+    fn eval_assign_errors() {
+        assert_eq!(
+            eval_input("y = 1"),
+            Err(Error::IdentifierNotFound("y".to_string()))
+        );
+        assert_eq!(
+            eval_input("let a = [1]; a[1] = 2"),
+            Err(Error::IndexOutOfBounds {
+                index: 1,
+                length: 1
+            })
+        );
+        assert_eq!(
+            eval_input(r#"let h = {}; h["a"] += 1"#),
+            Err(Error::KeyNotFound("a".to_string()))
+        );
+        assert_eq!(
+            eval_input("let x = true; x += 1"),
+            Err(Error::TypeMismatch("BOOLEAN + INTEGER".to_string()))
+        );
+        assert_eq!(
+            eval_input("let x = 1; x[0] = 1"),
+            Err(Error::IndexNotSupported("INTEGER[INTEGER]".to_string()))
+        );
+    }
+
+    #[test]
+    fn eval_if_expressions() {
+        assert_eq!(eval_input("if (true) { 10 }"), Ok(Object::Integer(10)));
+        assert_eq!(eval_input("if (false) { 10 }"), Ok(Object::Null));
+        assert_eq!(eval_input("if (1) { 10 }"), Ok(Object::Integer(10)));
+        assert_eq!(
+            eval_input("if (1 >= 2) { 10 } else { 20 }"),
+            Ok(Object::Integer(20))
+        );
+        assert_eq!(
+            eval_input(
+                "let grade = fn(score) { \
+                    if (score >= 90) { \"A\" } \
+                    else if (score >= 80) { \"B\" } \
+                    else if (score >= 70) { \"C\" } \
+                    else { \"F\" } \
+                }; \
+                grade(95) + grade(85) + grade(75) + grade(10)"
+            ),
+            Ok(Object::String("ABCF".into()))
+        );
+        assert_eq!(
+            eval_input("if (false) { 1 } else if (false) { 2 }"),
+            Ok(Object::Null)
+        );
+    }
+
+    #[test]
+    fn eval_match_expressions() {
+        let describe = r#"let describe = fn(x) {
+            match (x) {
+                0 => "zero",
+                -1 => "minus one",
+                "hi" => "greeting",
+                true => "yes",
+                [] => "empty",
+                [a] => "one " + a,
+                [a, [b, _]] => { let c = a + b; "nested " + c }
+                [a, ...rest] => "many",
+                {kind} => "kind " + kind,
+                _ => "other"
+            }
+        };"#;
+
+        for (value, expected) in [
+            ("0", "zero"),
+            ("-1", "minus one"),
+            (r#""hi""#, "greeting"),
+            ("true", "yes"),
+            ("[]", "empty"),
+            (r#"["x"]"#, "one x"),
+            (r#"["a", ["b", 1]]"#, "nested ab"),
+            ("[1, 2, 3]", "many"),
+            (r#"{"kind": "x"}"#, "kind x"),
+            ("{}", "other"),
+            ("false", "other"),
+        ] {
+            assert_eq!(
+                eval_input(&format!("{describe} describe({value})")),
+                Ok(Object::String(expected.into()))
+            );
+        }
+
+        // bindings don't leak out of the arm
+        assert_eq!(
+            eval_input("let a = 1; match (2) { a => a }; a"),
+            Ok(Object::Integer(1))
+        );
+        assert_eq!(
+            eval_input("match (3) { 1 => 1, 2 => 2 }"),
+            Err(Error::NoMatchingArm("3".to_string()))
+        );
+
+        // integers too big for an i64 match big integer literals
+        let big = r#"let big = fn(x) {
+            match (x) {
+                18446744073709551616 => "2^64",
+                -9223372036854775809 => "below i64",
+                -9223372036854775808 => "i64 min",
+                _ => "other"
+            }
+        };"#;
+        for (value, expected) in [
+            ("pow(2, 64)", "2^64"),
+            ("18446744073709551616", "2^64"),
+            ("-9223372036854775807 - 2", "below i64"),
+            ("-9223372036854775807 - 1", "i64 min"),
+            ("pow(2, 64) - 1", "other"),
+            ("0", "other"),
+        ] {
+            assert_eq!(
+                eval_input(&format!("{big} big({value})")),
+                Ok(Object::String(expected.into()))
+            );
+        }
+    }
+
+    #[test]
+    fn eval_function_application() {
+        assert_eq!(
+            eval_input("let identity = fn(x) { x; }; identity(5);"),
+            Ok(Object::Integer(5))
+        );
+        assert_eq!(
+            eval_input("let double = fn(x) { return x * 2; }; double(5);"),
+            Ok(Object::Integer(10))
+        );
+        assert_eq!(
+            eval_input("let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5));"),
+            Ok(Object::Integer(20))
+        );
+        assert_eq!(eval_input("fn(x) { x; }(5)"), Ok(Object::Integer(5)));
+        assert_eq!(
+            eval_input("let adder = fn(x) { fn(y) { x + y } }; let addTwo = adder(2); addTwo(3);"),
+            Ok(Object::Integer(5))
+        );
+        assert_eq!(
+            eval_input("let fact = fn(n) { if (n <= 1) { 1 } else { n * fact(n - 1) } }; fact(5);"),
+            Ok(Object::Integer(120))
+        );
+    }
+
+    #[test]
+    fn eval_function_errors() {
+        assert_eq!(
+            eval_input("let a = 5; a(1);"),
+            Err(Error::NotAFunction("INTEGER".to_string()))
+        );
+        assert_eq!(
+            eval_input("fn(x, y) { x }(1)"),
+            Err(Error::MissingArgument("y".to_string()))
+        );
+        assert_eq!(
+            eval_input("fn(x, y = 1) { x }(1, 2, 3)"),
+            Err(Error::WrongNumberOfArguments {
+                expected: 2,
+                got: 3
+            })
+        );
+        assert_eq!(
+            eval_input("fn(x) { x }(y: 1)"),
+            Err(Error::UnknownParameter("y".to_string()))
+        );
+        assert_eq!(
+            eval_input("fn(x) { x }(1, x: 2)"),
+            Err(Error::DuplicateArgument("x".to_string()))
+        );
+        assert_eq!(
+            eval_input("fn(x, ...rest) { x }(rest: [1])"),
+            Err(Error::UnknownParameter("rest".to_string()))
+        );
+    }
+
+    #[test]
+    fn eval_default_variadic_and_named_parameters() {
+        assert_eq!(
+            eval_input("let f = fn(x, y = 10) { x + y }; [f(1), f(1, 2), f(y: 3, x: 1)]")
+                .map(|value| value.to_string()),
+            Ok("[11, 3, 4]".to_string())
+        );
+        assert_eq!(
+            eval_input("let f = fn(x, y = x * 2) { y }; f(4)"),
+            Ok(Object::Integer(8))
+        );
+        assert_eq!(
+            eval_input("let f = fn(x, y = 10, ...rest) { [x, y, rest] }; [f(1), f(1, 2, 3, 4)]")
+                .map(|value| value.to_string()),
+            Ok("[[1, 10, []], [1, 2, [3, 4]]]".to_string())
+        );
+        assert_eq!(
+            eval_input("let f = fn(x, y = 10, ...rest) { [x, y, rest] }; f(1, y: 2)")
+                .map(|value| value.to_string()),
+            Ok("[1, 2, []]".to_string())
+        );
+        // defaults only see the parameters before them, a later one
+        // resolves to the scope the function was defined in
+        assert_eq!(
+            eval_input("let b = 1; let f = fn(a = b, b = 2) { [a, b] }; [f(), f(b: 3)]")
+                .map(|value| value.to_string()),
+            Ok("[[1, 2], [1, 3]]".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_tail_calls() {
+        assert_eq!(
+            eval_input(
+                "let sum = fn(n, total = 0) { if (n == 0) { total } else { sum(n - 1, total + n) } }; \
+                sum(1000000)"
+            ),
+            Ok(Object::Integer(500000500000))
+        );
+        assert_eq!(
+            eval_input(
+                "let count = fn(n) { while (true) { if (n == 0) { return 0; } return count(n - 1); } }; \
+                count(100000)"
+            ),
+            Ok(Object::Integer(0))
+        );
+        assert_eq!(
+            eval_input(
+                "let even = fn(n) { if (n == 0) { true } else { odd(n - 1) } }; \
+                let odd = fn(n) { if (n == 0) { false } else { even(n - 1) } }; \
+                even(100001)"
+            ),
+            Ok(Object::Boolean(false))
+        );
+        // a call in a try block isn't a tail call, its errors are still caught
+        assert_eq!(
+            eval_input(
+                r#"let g = fn() { throw "x"; }; let f = fn() { try { g() } catch (e) { "caught" } }; f()"#
+            ),
+            Ok(Object::String("caught".into()))
+        );
+        assert_eq!(
+            eval_input(
+                r#"let g = fn() { throw "x"; }; let f = fn() { try { return g(); } catch (e) { "caught" } }; f()"#
+            ),
+            Ok(Object::String("caught".into()))
+        );
+    }
+
+    #[test]
+    fn eval_try_expressions() {
+        for (input, expected) in [
+            (
+                r#"try { throw "boom"; 1 } catch (e) { e["message"] }"#,
+                "boom",
+            ),
+            (r#"try { 1 } catch (e) { 2 }"#, "1"),
+            (
+                r#"try { throw {"code": 1}; } catch (e) { e["value"]["code"] }"#,
+                "1",
+            ),
+            (
+                r#"try { 1 + true } catch (e) { e["message"] }"#,
+                "type mismatch: INTEGER + BOOLEAN",
+            ),
+            (
+                r#"try { [1][0] + x } catch (e) { e["value"] }"#,
+                "identifier not found: x",
+            ),
+            (
+                r#"let f = fn() { throw "inner"; }; let g = fn() { f() };
+                try { g() } catch (e) { map(e["stack"], fn(frame) { frame["function"] }) }"#,
+                r#"["f", "g"]"#,
+            ),
+            (
+                r#"let f = fn() { throw "x"; };
+                try { f() } catch (e) { let [frame] = e["stack"]; [frame["line"], frame["column"]] }"#,
+                "[2, 23]",
+            ),
+            // errors can be rethrown from the catch block and caught again
+            (
+                r#"try { try { throw "a"; } catch (e) { throw e["message"] + "b"; } } catch (e) { e["message"] }"#,
+                "ab",
+            ),
+            // the caught error is only bound inside the catch block
+            (r#"let e = 1; try { throw 2; } catch (e) { e }; e"#, "1"),
+        ] {
+            assert_eq!(
+                eval_input(input).map(|value| value.to_string()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn eval_try_control_flow() {
+        // return leaves the function through the try block, without running the catch
+        assert_eq!(
+            eval_input(r#"let f = fn() { try { return 1; } catch (e) { 2 }; 3 }; f()"#),
+            Ok(Object::Integer(1))
+        );
+        assert_eq!(
+            eval_input(
+                r#"let f = fn() { try { throw 1; } catch (e) { return e["value"] + 1; }; 3 }; f()"#
+            ),
+            Ok(Object::Integer(2))
+        );
+        assert_eq!(
+            eval_input(
+                "let n = 0; \
+                while (true) { try { n += 1; if (n == 3) { break; } continue; } catch (e) { 0 } } \
+                n"
+            ),
+            Ok(Object::Integer(3))
+        );
+        // statements after a throw don't run
+        assert_eq!(
+            eval_input(r#"let n = 0; try { throw 1; n = 1; } catch (e) { 0 }; n"#),
+            Ok(Object::Integer(0))
+        );
+    }
+
+    #[test]
+    fn eval_uncaught_throw() {
+        assert_eq!(
+            eval_input(r#"throw "boom";"#),
+            Err(Error::Thrown(Object::String("boom".into())))
+        );
+        assert_eq!(
+            eval_input(r#"fn() { throw [1]; }()"#).map_err(|error| error.to_string()),
+            Err("[1]".to_string())
+        );
+    }
+}
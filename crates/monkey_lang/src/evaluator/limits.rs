@@ -0,0 +1,503 @@
+use crate::error::Error;
+use crate::evaluator::{Evaluator, Unwind};
+use crate::object::Object;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Leaves room for the host in a 2MB thread, the default size for spawned threads
+const DEFAULT_MAX_STACK_SIZE: usize = 1024 * 1024;
+
+/// Reading the clock is slower than the rest of a step, so the deadline is
+/// only checked once every this many steps
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// Bounds on the work a program can do, a program that goes over one of them
+/// stops with a limit exceeded error that try/catch can't catch
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Most expressions evaluated by a single call to eval_program or eval_file
+    pub max_steps: Option<u64>,
+    /// Deepest nesting of function calls
+    pub max_call_depth: Option<usize>,
+    /// Most elements an array or hash, or bytes a string or integer, can hold
+    pub max_collection_size: Option<usize>,
+    /// Deepest nesting of arrays and hashes inside each other. Checking it
+    /// goes through the values put in a collection, so it makes building
+    /// collections of collections slower
+    pub max_depth: Option<usize>,
+    /// Most bytes of the Rust stack evaluation can use, the evaluator recurses
+    /// on the Rust stack so this has to be less than the stack of the thread
+    /// it runs on
+    pub max_stack_size: usize,
+    /// Longest a single call to eval_program or eval_file can run for
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_steps: None,
+            max_call_depth: None,
+            max_collection_size: None,
+            max_depth: None,
+            max_stack_size: DEFAULT_MAX_STACK_SIZE,
+            timeout: None,
+        }
+    }
+}
+
+/// The limit a program went over, along with its configured value
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Limit {
+    Steps(u64),
+    CallDepth(usize),
+    CollectionSize(usize),
+    Depth(usize),
+    StackSize(usize),
+    Timeout(Duration),
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::Steps(max) => f.write_str(&format!("{max} steps")),
+            Limit::CallDepth(max) => f.write_str(&format!("call depth of {max}")),
+            Limit::CollectionSize(max) => f.write_str(&format!("collection size of {max}")),
+            Limit::Depth(max) => f.write_str(&format!("nesting depth of {max}")),
+            Limit::StackSize(max) => f.write_str(&format!("stack size of {max} bytes")),
+            Limit::Timeout(max) => f.write_str(&format!("timeout of {max:?}")),
+        }
+    }
+}
+
+/// Stops a running program from another thread. The program stops with a
+/// cancelled error the next time it evaluates an expression, and so does
+/// every later run, a cancelled handle stays cancelled
+#[derive(Debug, Clone, Default)]
+pub struct CancellationHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Usage counted against the limits during a run
+#[derive(Debug, Default)]
+pub(crate) struct Usage {
+    steps: u64,
+    call_depth: usize,
+    /// Address of the stack when the outermost run started
+    stack_base: Option<usize>,
+    deadline: Option<Instant>,
+}
+
+impl Evaluator {
+    /// Runs f as a top level evaluation, usage is counted from zero unless
+    /// this is nested inside another run, e.g. eval_program inside eval_file
+    pub(crate) fn run<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let outermost = self.usage.stack_base.is_none();
+        if outermost {
+            // nothing is being evaluated, so every live scope that isn't
+            // garbage can be reached from the globals
+            if self.collector.should_collect() {
+                self.collect_garbage();
+            }
+            self.usage = Usage {
+                stack_base: Some(stack_address()),
+                deadline: self.limits.timeout.map(|timeout| Instant::now() + timeout),
+                ..Usage::default()
+            };
+        }
+
+        // a run starts outside of any function, where there are no tail calls
+        let result = self.with_tail_calls(false, f);
+
+        if outermost {
+            self.usage.stack_base = None;
+        }
+        result
+    }
+
+    /// Called before every expression, the point where a program can be stopped
+    pub(crate) fn count_step(&mut self) -> Result<(), Unwind> {
+        if self.cancellation.is_cancelled() {
+            return Err(Error::Cancelled.into());
+        }
+
+        // every level of nesting, whether calls or nested expressions, goes
+        // through here so this catches anything that could overflow the stack
+        if let Some(stack_base) = self.usage.stack_base {
+            let max = self.limits.max_stack_size;
+            if stack_base.abs_diff(stack_address()) > max {
+                return Err(Error::LimitExceeded(Limit::StackSize(max)).into());
+            }
+        }
+
+        self.usage.steps += 1;
+        if let Some(max) = self.limits.max_steps {
+            if self.usage.steps > max {
+                return Err(Error::LimitExceeded(Limit::Steps(max)).into());
+            }
+        }
+        if let (Some(deadline), Some(timeout)) = (self.usage.deadline, self.limits.timeout) {
+            if self.usage.steps.is_multiple_of(DEADLINE_CHECK_INTERVAL)
+                && Instant::now() >= deadline
+            {
+                return Err(Error::LimitExceeded(Limit::Timeout(timeout)).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the call depth before calling a function, exit_call
+    /// has to be called once the call is done
+    pub(crate) fn enter_call(&mut self) -> Result<(), Unwind> {
+        if let Some(max) = self.limits.max_call_depth {
+            if self.usage.call_depth >= max {
+                return Err(Error::LimitExceeded(Limit::CallDepth(max)).into());
+            }
+        }
+
+        self.usage.call_depth += 1;
+        Ok(())
+    }
+
+    pub(crate) fn exit_call(&mut self) {
+        self.usage.call_depth -= 1;
+    }
+
+    pub(crate) fn check_collection_size(&self, value: &Object) -> Result<(), Unwind> {
+        let Some(max) = self.limits.max_collection_size else {
+            return Ok(());
+        };
+
+        let size = match value {
+            Object::String(value) => value.len(),
+            Object::BigInteger(value) => value.bits().div_ceil(8) as usize,
+            Object::Array(elements) => elements.len(),
+            Object::Hash(pairs) => pairs.len(),
+            _ => 0,
+        };
+        if size > max {
+            return Err(Error::LimitExceeded(Limit::CollectionSize(max)).into());
+        }
+        Ok(())
+    }
+
+    /// Checks that value can be put nesting levels deep inside a collection,
+    /// a new collection is checked with a nesting of 0. Collections that
+    /// already exist passed this check, so only the values going into one
+    /// need to be checked
+    pub(crate) fn check_depth(&self, value: &Object, nesting: usize) -> Result<(), Unwind> {
+        let Some(max) = self.limits.max_depth else {
+            return Ok(());
+        };
+
+        let mut pending = vec![(value, nesting)];
+        while let Some((value, depth)) = pending.pop() {
+            if !matches!(value, Object::Array(_) | Object::Hash(_)) {
+                continue;
+            }
+            if depth >= max {
+                return Err(Error::LimitExceeded(Limit::Depth(max)).into());
+            }
+            match value {
+                Object::Array(elements) => {
+                    pending.extend(elements.iter().map(|element| (element, depth + 1)))
+                }
+                Object::Hash(pairs) => {
+                    pending.extend(pairs.values().map(|value| (value, depth + 1)))
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Address of a local in the current frame, the distance between two of
+/// these is roughly the stack used between them
+#[inline(never)]
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{Expression, Program, Statement};
+    use crate::error::Error;
+    use crate::evaluator::limits::{CancellationHandle, Limit, Limits};
+    use crate::evaluator::{Evaluator, Options};
+    use crate::lexer::Lexer;
+    use crate::object::Object;
+    use crate::parser::Parser;
+    use crate::token::Span;
+    use std::rc::Rc;
+    use std::thread;
+    use std::time::Duration;
+
+    fn eval_with_limits(input: &str, limits: Limits) -> Result<Object, Error> {
+        let program = Parser::new(Lexer::new(input.chars()))
+            .parse_program()
+            .unwrap();
+        Evaluator::with_options(Options {
+            limits,
+            ..Options::default()
+        })
+        .eval_program(&program)
+        .map_err(|error| error.error)
+    }
+
+    #[test]
+    fn step_limit() {
+        let limits = Limits {
+            max_steps: Some(1000),
+            ..Limits::default()
+        };
+        assert_eq!(
+            eval_with_limits("while (true) {}", limits.clone()),
+            Err(Error::LimitExceeded(Limit::Steps(1000)))
+        );
+        assert_eq!(
+            eval_with_limits("let i = 0; while (i < 10) { i += 1; } i", limits),
+            Ok(Object::Integer(10))
+        );
+    }
+
+    #[test]
+    fn call_depth_limit() {
+        let limits = Limits {
+            max_call_depth: Some(20),
+            ..Limits::default()
+        };
+        assert_eq!(
+            eval_with_limits("let f = fn(f) { 1 + f(f) }; f(f)", limits.clone()),
+            Err(Error::LimitExceeded(Limit::CallDepth(20)))
+        );
+        assert_eq!(
+            eval_with_limits(
+                "let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } }; f(19)",
+                limits
+            ),
+            Ok(Object::Integer(19))
+        );
+    }
+
+    #[test]
+    fn unbounded_recursion_stops_before_overflowing_the_stack() {
+        assert_eq!(
+            eval_with_limits("let f = fn(f) { 1 + f(f) }; f(f)", Limits::default()),
+            Err(Error::LimitExceeded(Limit::StackSize(1024 * 1024)))
+        );
+    }
+
+    #[test]
+    fn collection_size_limit() {
+        let limits = Limits {
+            max_collection_size: Some(100),
+            ..Limits::default()
+        };
+        for input in [
+            "let a = []; while (true) { a = push(a, 1); }",
+            r#"let s = "a"; while (true) { s += s; }"#,
+            "let h = {}; let i = 0; while (true) { h[i] = i; i += 1; }",
+            "range(0, 1000)",
+            "let x = 2; while (true) { x = x * x; }",
+            // 801 bits, one more than fits in 100 bytes
+            "pow(2, 800)",
+        ] {
+            assert_eq!(
+                eval_with_limits(input, limits.clone()),
+                Err(Error::LimitExceeded(Limit::CollectionSize(100))),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            eval_with_limits("len(range(0, 100))", limits.clone()),
+            Ok(Object::Integer(100))
+        );
+        assert_eq!(
+            eval_with_limits("pow(2, 799) > 0", limits),
+            Ok(Object::Boolean(true))
+        );
+    }
+
+    #[test]
+    fn depth_limit() {
+        let limits = Limits {
+            max_depth: Some(3),
+            ..Limits::default()
+        };
+        for input in [
+            "let a = []; while (true) { a = [a]; }",
+            r#"let h = {}; while (true) { h = {"h": h}; }"#,
+            "let a = []; while (true) { a = push([], a); }",
+            "let a = [[[]]]; a[0][0][0] = [];",
+            "let a = [[[]]]; map(a, fn(x) { a })",
+            "let f = fn(...rest) { f(rest) }; f()",
+            r#"json_parse("[[[[]]]]")"#,
+        ] {
+            assert_eq!(
+                eval_with_limits(input, limits.clone()),
+                Err(Error::LimitExceeded(Limit::Depth(3))),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            eval_with_limits("let a = [[[]]]; a[0][0] = [1]; a", limits),
+            Ok(Object::Array(Rc::new(vec![Object::Array(Rc::new(vec![
+                Object::Array(Rc::new(vec![Object::Integer(1)]))
+            ]))])))
+        );
+    }
+
+    /// Runs f on a thread with the 2MB stack spawned threads get by default
+    fn on_small_stack(f: impl FnOnce() + Send + 'static) {
+        thread::Builder::new()
+            .stack_size(2 * 1024 * 1024)
+            .spawn(f)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn deep_nesting_doesnt_overflow_the_stack() {
+        on_small_stack(|| {
+            let input = "let a = []; let b = []; let i = 0;
+                while (i < 100000) { a = [a]; b = [b]; i += 1; }
+                [len(str(a)), len(json_stringify(a)), a == b]";
+            assert_eq!(
+                eval_with_limits(input, Limits::default()).map(|value| value.to_string()),
+                Ok("[200002, 200002, true]".to_string())
+            );
+
+            // every closure holds on to the one before it
+            let input = "let make = fn(previous) { fn() { previous } };
+                let f = fn() { 0 }; let i = 0;
+                while (i < 100000) { f = make(f); i += 1; }";
+            assert_eq!(eval_with_limits(input, Limits::default()), Ok(Object::Null));
+
+            let input = "[".repeat(10000) + &"]".repeat(10000);
+            assert_eq!(
+                Parser::new(Lexer::new(input.chars())).parse_program(),
+                Err(Error::NestedTooDeeply(256))
+            );
+
+            // the parser won't nest this deeply, but a program built by hand can
+            let mut expression = Expression::IntegerLiteral(1);
+            for _ in 0..10000 {
+                expression = Expression::Prefix {
+                    operator: "-".to_string(),
+                    right: Box::new(expression),
+                    span: Span::default(),
+                };
+            }
+            let program = Program::new(vec![Statement::Expression(expression)]);
+            assert_eq!(
+                Evaluator::new()
+                    .eval_program(&program)
+                    .map_err(|error| error.error),
+                Err(Error::LimitExceeded(Limit::StackSize(1024 * 1024)))
+            );
+        });
+    }
+
+    #[test]
+    fn timeout() {
+        let limits = Limits {
+            timeout: Some(Duration::from_millis(50)),
+            ..Limits::default()
+        };
+        assert_eq!(
+            eval_with_limits("while (true) {}", limits.clone()),
+            Err(Error::LimitExceeded(Limit::Timeout(Duration::from_millis(
+                50
+            ))))
+        );
+        assert_eq!(eval_with_limits("1 + 1", limits), Ok(Object::Integer(2)));
+    }
+
+    #[test]
+    fn cancel_from_another_thread() {
+        let cancellation = CancellationHandle::new();
+        let mut evaluator = Evaluator::with_options(Options {
+            cancellation: cancellation.clone(),
+            ..Options::default()
+        });
+        let program = Parser::new(Lexer::new("while (true) {}".chars()))
+            .parse_program()
+            .unwrap();
+
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            cancellation.cancel();
+        });
+        assert_eq!(
+            evaluator
+                .eval_program(&program)
+                .map_err(|error| error.error),
+            Err(Error::Cancelled)
+        );
+        canceller.join().unwrap();
+        assert!(evaluator.cancellation_handle().is_cancelled());
+    }
+
+    #[test]
+    fn limits_cant_be_caught() {
+        let limits = Limits {
+            max_steps: Some(1000),
+            ..Limits::default()
+        };
+        assert_eq!(
+            eval_with_limits(
+                r#"let n = 0; while (true) { try { while (true) {} } catch (e) { n += 1; } }"#,
+                limits
+            ),
+            Err(Error::LimitExceeded(Limit::Steps(1000)))
+        );
+
+        let mut evaluator = Evaluator::new();
+        evaluator.cancellation_handle().cancel();
+        let program = Parser::new(Lexer::new("try { 1 } catch (e) { 2 }".chars()))
+            .parse_program()
+            .unwrap();
+        assert_eq!(
+            evaluator
+                .eval_program(&program)
+                .map_err(|error| error.error),
+            Err(Error::Cancelled)
+        );
+    }
+
+    #[test]
+    fn usage_resets_between_runs() {
+        let mut evaluator = Evaluator::with_options(Options {
+            limits: Limits {
+                max_steps: Some(100),
+                ..Limits::default()
+            },
+            ..Options::default()
+        });
+        let program = Parser::new(Lexer::new("let i = 0; while (i < 10) { i += 1; }".chars()))
+            .parse_program()
+            .unwrap();
+        for _ in 0..10 {
+            assert_eq!(evaluator.eval_program(&program), Ok(Object::Null));
+        }
+    }
+}
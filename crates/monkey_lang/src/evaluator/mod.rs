@@ -0,0 +1,559 @@
+mod builtin;
+pub mod capabilities;
+mod collector;
+pub mod environment;
+mod expression;
+pub mod limits;
+mod module;
+mod pattern;
+mod statement;
+
+use crate::ast::Program;
+use crate::error::{Error, RuntimeError};
+use crate::evaluator::builtin::builtins;
+use crate::evaluator::capabilities::Capabilities;
+use crate::evaluator::collector::Collector;
+use crate::evaluator::environment::{Env, Environment};
+use crate::evaluator::limits::{CancellationHandle, Limits, Usage};
+use crate::evaluator::module::ModuleLoader;
+use crate::lexer::Lexer;
+use crate::object::Object;
+use crate::parser::Parser;
+use crate::token::Span;
+use num_bigint::{BigInt, Sign};
+use std::rc::Rc;
+
+/// Reasons evaluation can stop before reaching the end of a block
+#[derive(Debug)]
+pub(crate) enum Unwind {
+    /// A runtime error, stops the whole program. Boxed to keep results small,
+    /// they live in every frame of the evaluator's recursion
+    Error(Box<RuntimeError>),
+    /// A return statement, caught by the enclosing function call or program
+    Return(Object),
+    /// A break statement, caught by the innermost loop
+    Break,
+    /// A continue statement, caught by the innermost loop
+    Continue,
+    /// A call in tail position, made by the enclosing function call once
+    /// the caller's frame is gone so tail recursion doesn't grow the stack
+    TailCall(Box<TailCall>),
+}
+
+/// A call whose function and arguments have been evaluated but that hasn't run yet
+#[derive(Debug)]
+pub(crate) struct TailCall {
+    function: Object,
+    arguments: Vec<Object>,
+    named_arguments: Vec<(String, Object)>,
+    span: Span,
+}
+
+impl Unwind {
+    /// The error to stop with when the unwind got out of everything that should
+    /// have caught it. The parser only allows break and continue inside loops,
+    /// but a program can be built or rewritten by hand
+    pub(crate) fn into_error(self) -> RuntimeError {
+        match self {
+            Unwind::Error(error) => *error,
+            Unwind::Break => Error::OutsideLoop("break".to_string()).into(),
+            Unwind::Continue => Error::OutsideLoop("continue".to_string()).into(),
+            Unwind::Return(_) => Error::UnexpectedControlFlow("return".to_string()).into(),
+            Unwind::TailCall(_) => Error::UnexpectedControlFlow("tail call".to_string()).into(),
+        }
+    }
+}
+
+impl From<Error> for Unwind {
+    fn from(error: Error) -> Self {
+        Unwind::Error(Box::new(error.into()))
+    }
+}
+
+pub(crate) type EvalResult = Result<Object, Unwind>;
+
+/// Functions written in Monkey that are loaded into every evaluator. It
+/// started out with map, filter, reduce, range, zip, sort and join, all but
+/// sort are builtins now: map, filter and reduce so they don't use a call
+/// frame per element, join to go with the other string builtins, and range
+/// and zip so they don't copy the array they build for every element
+const PRELUDE: &str = include_str!("prelude.mk");
+
+/// Settings for a new evaluator
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Whether to load the prelude (sort), sandboxed hosts can turn this off
+    /// to control exactly what scripts can call. map, filter, reduce, range,
+    /// zip and join are builtins, so they are there either way
+    pub prelude: bool,
+    pub limits: Limits,
+    /// Lets the host stop the evaluator's programs from another thread
+    pub cancellation: CancellationHandle,
+    pub overflow: Overflow,
+    /// What scripts can access outside the evaluator, nothing by default
+    pub capabilities: Capabilities,
+}
+
+/// What integer arithmetic does when the result doesn't fit in 64 bits
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Overflow {
+    /// Switch to an arbitrary precision integer
+    #[default]
+    Promote,
+    /// Raise an integer overflow error
+    Error,
+    /// Wrap around in two's complement
+    Wrap,
+}
+
+impl Overflow {
+    /// Turns the exact result of integer arithmetic into an object.
+    /// describe says what overflowed for the error message
+    pub(crate) fn fit(
+        self,
+        value: BigInt,
+        describe: impl FnOnce() -> String,
+    ) -> Result<Object, Error> {
+        if let Ok(value) = i64::try_from(&value) {
+            return Ok(Object::Integer(value));
+        }
+        match self {
+            Overflow::Promote => Ok(Object::BigInteger(Rc::new(value))),
+            Overflow::Error => Err(Error::IntegerOverflow(describe())),
+            Overflow::Wrap => {
+                // two's complement keeps the lowest 64 bits of the magnitude,
+                // negated for negative values
+                let (sign, digits) = value.to_u64_digits();
+                let low = digits.first().copied().unwrap_or_default() as i64;
+                Ok(Object::Integer(if sign == Sign::Minus {
+                    low.wrapping_neg()
+                } else {
+                    low
+                }))
+            }
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            prelude: true,
+            limits: Limits::default(),
+            cancellation: CancellationHandle::new(),
+            overflow: Overflow::default(),
+            capabilities: Capabilities::default(),
+        }
+    }
+}
+
+/// Tree walking evaluator, holds the global environment so state
+/// persists across calls to eval_program
+///
+/// Closures returned by eval_program are only valid until the next run, or
+/// until the evaluator is dropped, unless they're still bound in the global
+/// scope or exported by a module. Reference cycles between closures and the
+/// scopes they capture are freed by clearing those scopes, and a value only
+/// the host holds can't be told apart from one kept alive by a cycle, so
+/// calling it afterwards fails with identifier not found
+pub struct Evaluator {
+    /// Holds the builtins and the prelude, the global scope of the program
+    /// and of every module is enclosed by it
+    root: Env,
+    env: Env,
+    modules: ModuleLoader,
+    collector: Collector,
+    limits: Limits,
+    usage: Usage,
+    cancellation: CancellationHandle,
+    /// Whether a return statement can make a tail call, only inside a function
+    /// and outside of try blocks, which have to stay on the stack to catch errors
+    tail_calls: bool,
+    overflow: Overflow,
+    capabilities: Capabilities,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Evaluator {
+    pub fn new() -> Self {
+        Self::with_options(Options::default())
+    }
+
+    pub fn with_options(options: Options) -> Self {
+        let root = Environment::new();
+        for builtin in builtins(&options.capabilities) {
+            root.borrow_mut()
+                .set(builtin.name, Object::Builtin(builtin));
+        }
+
+        let mut evaluator = Self {
+            env: Environment::new_enclosed(root.clone()),
+            root,
+            modules: ModuleLoader::default(),
+            collector: Collector::default(),
+            limits: Limits::default(),
+            usage: Usage::default(),
+            cancellation: options.cancellation,
+            tail_calls: false,
+            overflow: options.overflow,
+            capabilities: options.capabilities,
+        };
+        if options.prelude {
+            evaluator.load_prelude();
+        }
+        // set after loading the prelude so it isn't counted against them
+        evaluator.limits = options.limits;
+        evaluator
+    }
+
+    /// A handle that can stop this evaluator's programs from another thread
+    pub fn cancellation_handle(&self) -> CancellationHandle {
+        self.cancellation.clone()
+    }
+
+    fn load_prelude(&mut self) {
+        let lexer = Lexer::new(PRELUDE.chars());
+        let program = Parser::new(lexer)
+            .parse_program()
+            .expect("prelude should parse");
+        let root = self.root.clone();
+        for statement in &program.statements {
+            self.eval_statement(statement, &root)
+                .expect("prelude should evaluate");
+        }
+    }
+
+    /// Creates a global scope for a program or module
+    pub(crate) fn new_global_env(&self) -> Env {
+        Environment::new_enclosed(self.root.clone())
+    }
+
+    /// Evaluates every statement in the program, returning the value
+    /// of the last one (or of the first top level return)
+    pub fn eval_program(&mut self, program: &Program) -> Result<Object, RuntimeError> {
+        self.run(|evaluator| {
+            let env = evaluator.env.clone();
+            let mut result = Object::Null;
+
+            for statement in &program.statements {
+                match evaluator.eval_top_level_statement(statement, &env) {
+                    Ok(value) => result = value,
+                    Err(Unwind::Return(value)) => return Ok(value),
+                    Err(unwind) => return Err(unwind.into_error()),
+                }
+            }
+
+            Ok(result)
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::ast::visit::{walk_statement_mut, VisitorMut};
+    use crate::ast::{Expression, Pattern, Statement};
+    use crate::error::{Error, RuntimeError};
+    use crate::evaluator::limits::Limits;
+    use crate::evaluator::{Evaluator, Options, Overflow};
+    use crate::lexer::Lexer;
+    use crate::object::Object;
+    use crate::parser::Parser;
+    use std::time::Duration;
+
+    pub(crate) fn eval_input(input: &str) -> Result<Object, Error> {
+        eval_input_with_options(input, Options::default())
+    }
+
+    pub(crate) fn eval_input_with_options(input: &str, options: Options) -> Result<Object, Error> {
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program().unwrap();
+        Evaluator::with_options(options)
+            .eval_program(&program)
+            .map_err(|error| error.error)
+    }
+
+    #[test]
+    fn eval_program_keeps_global_state() {
+        let mut evaluator = Evaluator::new();
+
+        let program = Parser::new(Lexer::new("let a = 5;".chars()))
+            .parse_program()
+            .unwrap();
+        assert_eq!(evaluator.eval_program(&program), Ok(Object::Null));
+
+        let program = Parser::new(Lexer::new("a * 2".chars()))
+            .parse_program()
+            .unwrap();
+        assert_eq!(evaluator.eval_program(&program), Ok(Object::Integer(10)));
+    }
+
+    #[test]
+    fn eval_top_level_return() {
+        assert_eq!(eval_input("1; return 2; 3;"), Ok(Object::Integer(2)));
+    }
+
+    #[test]
+    fn eval_prelude_functions() {
+        for (input, expected) in [
+            ("range(5, 0, -2)", "[5, 3, 1]"),
+            ("range(0, 3, 0)", "[]"),
+            (r#"zip([1, 2, 3], ["a", "b"])"#, r#"[[1, "a"], [2, "b"]]"#),
+            ("sort([5, 3, 9, 1, 3])", "[1, 3, 3, 5, 9]"),
+            ("sort([1, 2, 3], fn(a, b) { a > b })", "[3, 2, 1]"),
+            (
+                "sort([[2, 1], [1, 2], [2, 3], [1, 4]], fn(a, b) { a[0] < b[0] })",
+                "[[1, 2], [1, 4], [2, 1], [2, 3]]",
+            ),
+        ] {
+            assert_eq!(
+                eval_input(input).map(|value| value.to_string()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+
+        // user definitions shadow the prelude without breaking it
+        assert_eq!(
+            eval_input("let less = 1; sort([2, 1])").map(|value| value.to_string()),
+            Ok("[1, 2]".to_string())
+        );
+    }
+
+    #[test]
+    fn range_zip_and_sort_scale_to_large_inputs() {
+        // building the result one push at a time used to copy it every time
+        let options = Options {
+            limits: Limits {
+                timeout: Some(Duration::from_secs(5)),
+                ..Limits::default()
+            },
+            ..Options::default()
+        };
+        for (input, expected) in [
+            (
+                "let n = 200000; let pairs = zip(range(0, n), range(n, 0, -1)); [len(pairs), pairs[n - 1]]",
+                "[200000, [199999, 1]]",
+            ),
+            (
+                "let sorted = sort(range(2000, 0, -1)); [len(sorted), sorted[0], sorted[1999]]",
+                "[2000, 1, 2000]",
+            ),
+        ] {
+            assert_eq!(
+                eval_input_with_options(input, options.clone()).map(|value| value.to_string()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn builtins_cant_be_reassigned() {
+        for name in ["len", "push", "range"] {
+            assert_eq!(
+                eval_input(&format!("{name} = fn(x) {{ 0 }};")),
+                Err(Error::AssignToBuiltin(name.to_string()))
+            );
+            let input = format!(
+                "try {{ {name} = fn(x) {{ 0 }}; }} catch (e) {{}}; [zip([1, 2], [3, 4]), range(0, 3)]"
+            );
+            assert_eq!(
+                eval_input(&input).map(|value| value.to_string()),
+                Ok("[[[1, 3], [2, 4]], [0, 1, 2]]".to_string()),
+                "{input}"
+            );
+        }
+
+        // a binding made with let shadows the builtin and can be assigned to
+        assert_eq!(
+            eval_input("let len = fn(x) { 0 }; len = fn(x) { 1 }; [len([]), zip([1, 2], [3, 4])]")
+                .map(|value| value.to_string()),
+            Ok("[1, [[1, 3], [2, 4]]]".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_without_prelude() {
+        let mut evaluator = Evaluator::with_options(Options {
+            prelude: false,
+            ..Options::default()
+        });
+        let program = Parser::new(Lexer::new("sort([2, 1])".chars()))
+            .parse_program()
+            .unwrap();
+        assert_eq!(
+            evaluator
+                .eval_program(&program)
+                .map_err(|error| error.error),
+            Err(Error::IdentifierNotFound("sort".to_string()))
+        );
+
+        // the functions that used to be in the prelude are builtins now
+        let input = r#"[
+            map([1, 2], fn(x) { x * 2 }),
+            filter(range(0, 4), fn(x) { x % 2 == 0 }),
+            reduce([1, 2, 3], 0, fn(total, x) { total + x }),
+            zip([1, 2], ["a", "b"]),
+            join(["a", "b"], "-")
+        ]"#;
+        let program = Parser::new(Lexer::new(input.chars()))
+            .parse_program()
+            .unwrap();
+        assert_eq!(
+            evaluator
+                .eval_program(&program)
+                .map(|value| value.to_string()),
+            Ok(r#"[[2, 4], [0, 2], 6, [[1, "a"], [2, "b"]], "a-b"]"#.to_string())
+        );
+    }
+
+    #[test]
+    fn eval_with_wrapping_overflow() {
+        let options = Options {
+            overflow: Overflow::Wrap,
+            ..Options::default()
+        };
+        assert_eq!(
+            eval_input_with_options(
+                "[9223372036854775807 + 1, -(-9223372036854775807 - 1), 4611686018427387904 * 2, pow(2, 64), abs(-9223372036854775807 - 1)]",
+                options.clone()
+            )
+            .map(|value| value.to_string()),
+            Ok("[-9223372036854775808, -9223372036854775808, -9223372036854775808, 0, -9223372036854775808]".to_string())
+        );
+        // big literals wrap as soon as they're used
+        assert_eq!(
+            eval_input_with_options(
+                "[100000000000000000000 * 3, pow(3, 4294967296), pow(-1, 4294967297)]",
+                options
+            )
+            .map(|value| value.to_string()),
+            Ok("[4852094820647174144, 2491309678558969857, -1]".to_string())
+        );
+    }
+
+    fn eval_runtime_error(input: &str) -> RuntimeError {
+        let program = Parser::new(Lexer::new(input.chars()))
+            .parse_program()
+            .unwrap();
+        Evaluator::new().eval_program(&program).unwrap_err()
+    }
+
+    #[test]
+    fn runtime_errors_record_span_and_stack() {
+        let error = eval_runtime_error(
+            "let add = fn(a, b) {\n  a + b\n};\nlet outer = fn(x) { add(x, true) };\nouter(1);",
+        );
+        assert_eq!(
+            error.error,
+            Error::TypeMismatch("INTEGER + BOOLEAN".to_string())
+        );
+        let span = error.span.unwrap();
+        assert_eq!((span.start.line, span.start.column), (2, 3));
+        assert_eq!((span.end.line, span.end.column), (2, 8));
+        assert_eq!(
+            error
+                .stack
+                .iter()
+                .map(|frame| (frame.function.as_str(), frame.call_site.start.to_string()))
+                .collect::<Vec<_>>(),
+            vec![("add", "4:21".to_string()), ("outer", "5:1".to_string())]
+        );
+        assert_eq!(
+            error.to_string(),
+            "type mismatch: INTEGER + BOOLEAN at 2:3\n    in add called at 4:21\n    in outer called at 5:1"
+        );
+    }
+
+    #[test]
+    fn runtime_error_spans() {
+        // the innermost expression with a span is reported
+        let error = eval_runtime_error("let x = 1;\nx + [1, 2][y]");
+        assert_eq!(error.to_string(), "identifier not found: y at 2:12");
+
+        // including inside an interpolated string
+        let error = eval_runtime_error("let a = 1;\n\"a is ${a}, b is ${b}\"");
+        assert_eq!(error.to_string(), "identifier not found: b at 2:20");
+
+        let error = eval_runtime_error(r#"{[1]: 2} == 1"#);
+        assert_eq!(error.to_string(), "unusable as hash key: ARRAY at 1:1");
+
+        // errors at the top level have no stack, anonymous functions are named as such
+        let error = eval_runtime_error("fn() { 1 / 0 }()");
+        assert_eq!(
+            error.to_string(),
+            "division by zero at 1:8\n    in <anonymous> called at 1:1"
+        );
+
+        let error = eval_runtime_error("let [a] = [1, 2];");
+        assert_eq!(error.span, None);
+        assert!(error.stack.is_empty());
+    }
+
+    /// Replaces every `marker;` statement, to build programs the parser wouldn't accept
+    struct ReplaceMarker(Statement);
+
+    impl VisitorMut for ReplaceMarker {
+        fn visit_statement_mut(&mut self, statement: &mut Statement) {
+            if let Statement::Expression(Expression::Identifier(name, _)) = statement {
+                if name == "marker" {
+                    *statement = self.0.clone();
+                    return;
+                }
+            }
+            walk_statement_mut(self, statement);
+        }
+    }
+
+    #[test]
+    fn eval_programs_the_parser_would_reject() {
+        let export = Statement::Export(Box::new(Statement::Let {
+            target: Pattern::Identifier("a".to_string()),
+            value: Expression::IntegerLiteral(1),
+        }));
+        for (input, replacement, expected) in [
+            (
+                "marker;",
+                Statement::Break,
+                Error::OutsideLoop("break".to_string()),
+            ),
+            (
+                "let f = fn() { marker; }; let n = 0; while (n < 3) { n += 1; f(); }",
+                Statement::Continue,
+                Error::OutsideLoop("continue".to_string()),
+            ),
+            (
+                "map([1], fn(x) { marker; })",
+                Statement::Break,
+                Error::OutsideLoop("break".to_string()),
+            ),
+            ("if (true) { marker; }", export, Error::ExportNotAtTopLevel),
+            (
+                "fn() { marker; }()",
+                Statement::Import {
+                    path: "lib.mk".to_string(),
+                    alias: "lib".to_string(),
+                },
+                Error::ImportNotAtTopLevel,
+            ),
+        ] {
+            let mut program = Parser::new(Lexer::new(input.chars()))
+                .parse_program()
+                .unwrap();
+            ReplaceMarker(replacement).visit_program_mut(&mut program);
+            assert_eq!(
+                Evaluator::new()
+                    .eval_program(&program)
+                    .map_err(|error| error.error),
+                Err(expected),
+                "{program}"
+            );
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use crate::ast::{Program, Statement};
+use crate::error::{Error, RuntimeError};
+use crate::evaluator::environment::Env;
+use crate::evaluator::{EvalResult, Evaluator, Unwind};
+use crate::lexer::Lexer;
+use crate::object::{HashKey, Object};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Keeps track of imported files, each module is only evaluated once
+#[derive(Debug, Default)]
+pub(crate) struct ModuleLoader {
+    /// Exports of modules that finished loading, by canonical path
+    cache: HashMap<PathBuf, Object>,
+    /// Files currently being evaluated, innermost last, along with the path
+    /// they were imported as so cycles can be reported readably
+    loading: Vec<(PathBuf, String)>,
+}
+
+impl ModuleLoader {
+    /// Exports of every module loaded so far
+    pub(crate) fn exports(&self) -> impl Iterator<Item = &Object> {
+        self.cache.values()
+    }
+}
+
+impl Evaluator {
+    /// Evaluates the file in the global environment, imports in it are
+    /// resolved relative to the directory it's in
+    pub fn eval_file(&mut self, path: impl AsRef<Path>) -> Result<Object, RuntimeError> {
+        let display_path = path.as_ref().display().to_string();
+        let path = canonicalize(path.as_ref(), &display_path)?;
+        let program = parse_file(&path, &display_path)?;
+
+        self.modules.loading.push((path, display_path));
+        let result = self.eval_program(&program);
+        self.modules.loading.pop();
+        result
+    }
+
+    /// Loads the module at path, returning a hash of its exports. Relative paths
+    /// are resolved against the importing file, or the working directory when
+    /// the program didn't come from a file. Only modules the host allowed in
+    /// its capabilities can be loaded, nothing is read from disk otherwise
+    pub(crate) fn eval_import(&mut self, path: &str) -> EvalResult {
+        let resolved = match self.modules.loading.last() {
+            Some((importer, _)) => importer.parent().unwrap_or(importer).join(path),
+            None => PathBuf::from(path),
+        };
+        let resolved = self.capabilities.importable_path(&resolved, path)?;
+        let resolved = canonicalize(&resolved, path)?;
+
+        if let Some(exports) = self.modules.cache.get(&resolved) {
+            return Ok(exports.clone());
+        }
+
+        if let Some(start) = self
+            .modules
+            .loading
+            .iter()
+            .position(|(loading, _)| *loading == resolved)
+        {
+            let chain = self.modules.loading[start..]
+                .iter()
+                .map(|(_, display_path)| display_path.as_str())
+                .chain([path])
+                .collect::<Vec<&str>>()
+                .join(" -> ");
+            return Err(Error::ImportCycle(chain).into());
+        }
+
+        let program = parse_file(&resolved, path)?;
+        self.modules
+            .loading
+            .push((resolved.clone(), path.to_string()));
+        let exports = self.eval_module(&program);
+        self.modules.loading.pop();
+
+        let exports = exports?;
+        self.modules.cache.insert(resolved, exports.clone());
+        Ok(exports)
+    }
+
+    /// Runs the module in its own global scope, a top level return stops
+    /// the module early
+    fn eval_module(&mut self, program: &Program) -> EvalResult {
+        let env = self.new_global_env();
+        self.with_tail_calls(false, |evaluator| {
+            for statement in &program.statements {
+                match evaluator.eval_top_level_statement(statement, &env) {
+                    Ok(_) => {}
+                    Err(Unwind::Return(_)) => break,
+                    Err(unwind) => return Err(Unwind::Error(Box::new(unwind.into_error()))),
+                }
+            }
+            let exports = collect_exports(program, &env);
+            evaluator.check_depth(&exports, 0)?;
+            Ok(exports)
+        })
+    }
+}
+
+/// Builds a hash of the names bound by exported let statements, using
+/// the values they have once the module finished running
+fn collect_exports(program: &Program, env: &Env) -> Object {
+    let mut exports = BTreeMap::new();
+    for statement in &program.statements {
+        if let Statement::Export(statement) = statement {
+            if let Statement::Let { target, .. } = statement.as_ref() {
+                for name in target.bound_names() {
+                    // missing if the module returned before the export
+                    if let Some(value) = env.borrow().get(name) {
+                        exports.insert(HashKey::String(name.into()), value);
+                    }
+                }
+            }
+        }
+    }
+    Object::Hash(Rc::new(exports))
+}
+
+fn canonicalize(path: &Path, display_path: &str) -> Result<PathBuf, Error> {
+    path.canonicalize()
+        .map_err(|_| Error::ModuleNotFound(display_path.to_string()))
+}
+
+fn parse_file(path: &Path, display_path: &str) -> Result<Program, Error> {
+    let load_failed = |message: String| Error::ModuleLoadFailed {
+        path: display_path.to_string(),
+        message,
+    };
+
+    let input = fs::read_to_string(path).map_err(|error| load_failed(error.to_string()))?;
+    let lexer = Lexer::new(input.chars());
+    let mut parser = crate::parser::Parser::new(lexer);
+    parser
+        .parse_program()
+        .map_err(|error| load_failed(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::evaluator::capabilities::Capabilities;
+    use crate::evaluator::{Evaluator, Options};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Writes the files into a fresh directory under the system temp directory
+    fn write_modules(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("monkey_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    /// An evaluator that can import modules from dir
+    fn evaluator_importing_from(dir: &Path) -> Evaluator {
+        Evaluator::with_options(Options {
+            capabilities: Capabilities {
+                modules: vec![dir.to_path_buf()],
+                ..Capabilities::default()
+            },
+            ..Options::default()
+        })
+    }
+
+    #[test]
+    fn eval_imports() {
+        let dir = write_modules(
+            "imports",
+            &[
+                (
+                    "main.mk",
+                    r#"import "lib/math.mk" as math;
+                    import "lib/math.mk" as again;
+                    [math["square"](4), math["counter"], again["counter"], math["hidden"]]"#,
+                ),
+                (
+                    "lib/math.mk",
+                    r#"import "util.mk" as util;
+                    let hidden = 1;
+                    export let square = fn(x) { util["mul"](x, x) };
+                    export let counter = 0;
+                    counter += 1;"#,
+                ),
+                ("lib/util.mk", "export let mul = fn(a, b) { a * b };"),
+            ],
+        );
+
+        let mut evaluator = evaluator_importing_from(&dir);
+        assert_eq!(
+            evaluator
+                .eval_file(dir.join("main.mk"))
+                .map(|value| value.to_string()),
+            Ok("[16, 1, 1, null]".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_import_errors() {
+        let dir = write_modules(
+            "import_errors",
+            &[
+                ("a.mk", r#"import "b.mk" as b;"#),
+                ("b.mk", r#"import "sub/c.mk" as c;"#),
+                ("sub/c.mk", r#"import "../a.mk" as a;"#),
+                ("missing.mk", r#"import "nowhere.mk" as n;"#),
+                ("broken.mk", "let = 1;"),
+                ("uses_broken.mk", r#"import "broken.mk" as b;"#),
+            ],
+        );
+
+        let mut evaluator = evaluator_importing_from(&dir);
+        assert_eq!(
+            evaluator
+                .eval_file(dir.join("a.mk"))
+                .map_err(|error| error.error),
+            Err(Error::ImportCycle(format!(
+                "{} -> b.mk -> sub/c.mk -> ../a.mk",
+                dir.join("a.mk").display()
+            )))
+        );
+        assert_eq!(
+            evaluator
+                .eval_file(dir.join("missing.mk"))
+                .map_err(|error| error.error),
+            Err(Error::ModuleNotFound("nowhere.mk".to_string()))
+        );
+        assert_eq!(
+            evaluator
+                .eval_file(dir.join("uses_broken.mk"))
+                .map_err(|error| error.error),
+            Err(Error::ModuleLoadFailed {
+                path: "broken.mk".to_string(),
+                message: "unexpected token: =".to_string()
+            })
+        );
+        assert_eq!(
+            evaluator
+                .eval_file(dir.join("nope.mk"))
+                .map_err(|error| error.error),
+            Err(Error::ModuleNotFound(
+                dir.join("nope.mk").display().to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn modules_have_their_own_scope() {
+        let dir = write_modules("scope", &[("lib.mk", "export let get = fn() { secret };")]);
+
+        let mut evaluator = evaluator_importing_from(&dir);
+        let input = format!(
+            r#"let secret = 1; import "{}" as lib; lib["get"]()"#,
+            dir.join("lib.mk").display()
+        );
+        let lexer = crate::lexer::Lexer::new(input.chars());
+        let program = crate::parser::Parser::new(lexer).parse_program().unwrap();
+        assert_eq!(
+            evaluator
+                .eval_program(&program)
+                .map_err(|error| error.error),
+            Err(Error::IdentifierNotFound("secret".to_string()))
+        );
+        assert_eq!(
+            evaluator
+                .eval_program(&program)
+                .map_err(|error| error.error),
+            Err(Error::IdentifierNotFound("secret".to_string()))
+        );
+    }
+
+    #[test]
+    fn imports_need_capabilities() {
+        let dir = write_modules(
+            "import_capabilities",
+            &[
+                ("allowed/main.mk", r#"import "../private.mk" as p; p"#),
+                ("allowed/lib.mk", "export let a = 1;"),
+                ("private.mk", "export let secret = 1;"),
+            ],
+        );
+        let lib = dir.join("allowed/lib.mk").display().to_string();
+        let input = format!(r#"import "{lib}" as lib; lib"#);
+        let program = crate::parser::Parser::new(crate::lexer::Lexer::new(input.chars()))
+            .parse_program()
+            .unwrap();
+
+        // nothing can be imported by default, not even a file that exists
+        assert_eq!(
+            Evaluator::new()
+                .eval_program(&program)
+                .map_err(|error| error.error),
+            Err(Error::PermissionDenied(lib.clone()))
+        );
+        let missing = dir.join("missing.mk").display().to_string();
+        let input = format!(r#"import "{missing}" as m;"#);
+        let missing_program = crate::parser::Parser::new(crate::lexer::Lexer::new(input.chars()))
+            .parse_program()
+            .unwrap();
+        assert_eq!(
+            Evaluator::new()
+                .eval_program(&missing_program)
+                .map_err(|error| error.error),
+            Err(Error::PermissionDenied(missing))
+        );
+
+        let mut evaluator = evaluator_importing_from(&dir.join("allowed"));
+        assert_eq!(
+            evaluator
+                .eval_program(&program)
+                .map(|value| value.to_string()),
+            Ok(r#"{"a": 1}"#.to_string())
+        );
+        // the file given to eval_file runs, but can't import from outside
+        assert_eq!(
+            evaluator
+                .eval_file(dir.join("allowed/main.mk"))
+                .map_err(|error| error.error),
+            Err(Error::PermissionDenied("../private.mk".to_string()))
+        );
+    }
+
+    #[test]
+    fn imports_resolve_against_the_file_they_are_in() {
+        let dir = write_modules(
+            "import_relative",
+            &[
+                (
+                    "app/main.mk",
+                    r#"import "../lib/lib.mk" as lib; import "data.mk" as d; [lib["load"](), d["v"]]"#,
+                ),
+                ("app/data.mk", r#"export let v = "app";"#),
+                (
+                    "lib/lib.mk",
+                    r#"import "data.mk" as d; export let load = fn() { d["v"] };"#,
+                ),
+                ("lib/data.mk", r#"export let v = "lib";"#),
+                (
+                    "lib/nested.mk",
+                    r#"export let load = fn() { import "data.mk" as d; d["v"] };"#,
+                ),
+                ("app/nested.mk", r#"import "../lib/nested.mk" as n;"#),
+            ],
+        );
+
+        let mut evaluator = evaluator_importing_from(&dir);
+        assert_eq!(
+            evaluator
+                .eval_file(dir.join("app/main.mk"))
+                .map(|value| value.to_string()),
+            Ok(r#"["lib", "app"]"#.to_string())
+        );
+        // an import inside a function would run while another file is loading
+        assert_eq!(
+            evaluator
+                .eval_file(dir.join("app/nested.mk"))
+                .map_err(|error| error.error),
+            Err(Error::ModuleLoadFailed {
+                path: "../lib/nested.mk".to_string(),
+                message: Error::ImportNotAtTopLevel.to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use crate::ast::Pattern;
+use crate::error::Error;
+use crate::object::{HashKey, Object};
+use std::rc::Rc;
+
+/// Checks if value has the shape described by pattern, collecting the values
+/// that identifiers in the pattern bind to. The error describes the first
+/// part of the value that didn't fit
+pub(crate) fn bind_pattern(
+    pattern: &Pattern,
+    value: &Object,
+    bindings: &mut Vec<(String, Object)>,
+) -> Result<(), Error> {
+    match (pattern, value) {
+        (Pattern::Wildcard, _) => Ok(()),
+        (Pattern::Identifier(name) | Pattern::Rest(name), value) => {
+            bindings.push((name.clone(), value.clone()));
+            Ok(())
+        }
+        (Pattern::Integer(expected), Object::Integer(value)) if expected == value => Ok(()),
+        (Pattern::BigInteger(expected), Object::BigInteger(value)) if expected == &**value => {
+            Ok(())
+        }
+        (Pattern::String(expected), Object::String(value))
+            if expected.as_str() == value.as_ref() =>
+        {
+            Ok(())
+        }
+        (Pattern::Boolean(expected), Object::Boolean(value)) if expected == value => Ok(()),
+        (
+            Pattern::Integer(_) | Pattern::BigInteger(_) | Pattern::String(_) | Pattern::Boolean(_),
+            _,
+        ) => Err(Error::PatternMismatch(pattern.to_string())),
+        (Pattern::Array(patterns), Object::Array(elements)) => {
+            bind_array_pattern(patterns, elements, bindings)
+        }
+        (Pattern::Hash(keys), Object::Hash(pairs)) => {
+            for key in keys {
+                let value = pairs
+                    .get(&HashKey::String(key.as_str().into()))
+                    .ok_or_else(|| Error::KeyNotFound(key.clone()))?;
+                bindings.push((key.clone(), value.clone()));
+            }
+            Ok(())
+        }
+        (Pattern::Array(_) | Pattern::Hash(_), value) => Err(Error::CannotDestructure {
+            pattern: pattern.to_string(),
+            value: value.type_name().to_string(),
+        }),
+    }
+}
+
+/// Without a rest pattern the lengths have to be the same, with one the array
+/// needs at least as many elements as there are other patterns
+fn bind_array_pattern(
+    patterns: &[Pattern],
+    elements: &[Object],
+    bindings: &mut Vec<(String, Object)>,
+) -> Result<(), Error> {
+    let (patterns, rest) = match patterns.split_last() {
+        Some((Pattern::Rest(name), patterns)) => (patterns, Some(name)),
+        _ => (patterns, None),
+    };
+
+    if rest.is_none() && elements.len() != patterns.len() {
+        return Err(Error::LengthMismatch {
+            expected: patterns.len(),
+            got: elements.len(),
+        });
+    }
+    if elements.len() < patterns.len() {
+        return Err(Error::NotEnoughElements {
+            expected: patterns.len(),
+            got: elements.len(),
+        });
+    }
+
+    for (pattern, element) in patterns.iter().zip(elements) {
+        bind_pattern(pattern, element, bindings)?;
+    }
+    if let Some(name) = rest {
+        let remaining = elements[patterns.len()..].to_vec();
+        bindings.push((name.clone(), Object::Array(Rc::new(remaining))));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::Pattern;
+    use crate::error::Error;
+    use crate::evaluator::pattern::bind_pattern;
+    use crate::object::Object;
+    use std::rc::Rc;
+
+    #[test]
+    fn bind_array_pattern() {
+        let pattern = Pattern::Array(vec![
+            Pattern::Identifier("a".to_string()),
+            Pattern::Wildcard,
+            Pattern::Integer(3),
+        ]);
+        let value = Object::Array(Rc::new(vec![
+            Object::String("x".into()),
+            Object::Boolean(true),
+            Object::Integer(3),
+        ]));
+
+        let mut bindings = Vec::new();
+        assert_eq!(bind_pattern(&pattern, &value, &mut bindings), Ok(()));
+        assert_eq!(
+            bindings,
+            vec![("a".to_string(), Object::String("x".into()))]
+        );
+
+        let value = Object::Array(Rc::new(vec![Object::Integer(1), Object::Integer(3)]));
+        assert_eq!(
+            bind_pattern(&pattern, &value, &mut Vec::new()),
+            Err(Error::LengthMismatch {
+                expected: 3,
+                got: 2
+            })
+        );
+        assert_eq!(
+            bind_pattern(&pattern, &Object::Null, &mut Vec::new()),
+            Err(Error::CannotDestructure {
+                pattern: "[a, _, 3]".to_string(),
+                value: "NULL".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn bind_rest_pattern() {
+        let pattern = Pattern::Array(vec![
+            Pattern::Identifier("a".to_string()),
+            Pattern::Rest("rest".to_string()),
+        ]);
+        let value = Object::Array(Rc::new(vec![
+            Object::Integer(1),
+            Object::Integer(2),
+            Object::Integer(3),
+        ]));
+
+        let mut bindings = Vec::new();
+        assert_eq!(bind_pattern(&pattern, &value, &mut bindings), Ok(()));
+        assert_eq!(
+            bindings,
+            vec![
+                ("a".to_string(), Object::Integer(1)),
+                (
+                    "rest".to_string(),
+                    Object::Array(Rc::new(vec![Object::Integer(2), Object::Integer(3)]))
+                ),
+            ]
+        );
+
+        assert_eq!(
+            bind_pattern(
+                &pattern,
+                &Object::Array(Rc::new(Vec::new())),
+                &mut Vec::new()
+            ),
+            Err(Error::NotEnoughElements {
+                expected: 1,
+                got: 0
+            })
+        );
+    }
+}
@@ -0,0 +1,40 @@
+let sort = fn(items, less = fn(a, b) { a < b }) {
+    if (len(items) <= 1) {
+        return items;
+    }
+
+    let middle = len(items) / 2;
+    let left = range(0, middle);
+    let right = range(middle, len(items));
+    let i = 0;
+    for (item in items) {
+        if (i < middle) {
+            left[i] = item;
+        } else {
+            right[i - middle] = item;
+        }
+        i += 1;
+    }
+    let left = sort(left, less);
+    let right = sort(right, less);
+
+    let result = range(0, len(items));
+    let [l, r] = [0, 0];
+    while (l + r < len(items)) {
+        let take_right = if (l >= len(left)) {
+            true
+        } else if (r >= len(right)) {
+            false
+        } else {
+            less(right[r], left[l])
+        };
+        if (take_right) {
+            result[l + r] = right[r];
+            r += 1;
+        } else {
+            result[l + r] = left[l];
+            l += 1;
+        }
+    }
+    result
+};
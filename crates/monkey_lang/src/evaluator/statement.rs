@@ -0,0 +1,352 @@
+use crate::ast::{Block, Expression, Pattern, Statement};
+use crate::error::{Error, RuntimeError};
+use crate::evaluator::environment::Env;
+use crate::evaluator::pattern::bind_pattern;
+use crate::evaluator::{EvalResult, Evaluator, Unwind};
+use crate::object::Object;
+
+impl Evaluator {
+    pub(crate) fn eval_statement(&mut self, statement: &Statement, env: &Env) -> EvalResult {
+        self.eval_statement_in(statement, env, false)
+    }
+
+    /// Evaluates a statement at the top level of a program or module, the
+    /// only place an import or export can be. Imports only run while the file
+    /// they're in is loading, so relative paths are resolved against it
+    pub(crate) fn eval_top_level_statement(
+        &mut self,
+        statement: &Statement,
+        env: &Env,
+    ) -> EvalResult {
+        match statement {
+            Statement::Import { path, alias } => {
+                let exports = self.eval_import(path)?;
+                env.borrow_mut().set(alias, exports);
+                Ok(Object::Null)
+            }
+            Statement::Export(statement) => self.eval_statement(statement, env),
+            statement => self.eval_statement(statement, env),
+        }
+    }
+
+    /// Evaluates the statement, if tail is set and it's an expression it's in
+    /// tail position and calls in it can be made as tail calls
+    fn eval_statement_in(&mut self, statement: &Statement, env: &Env, tail: bool) -> EvalResult {
+        match statement {
+            Statement::Let { target, value } => {
+                let value = match (target, value) {
+                    (
+                        Pattern::Identifier(name),
+                        Expression::FunctionLiteral { parameters, body },
+                    ) => self.eval_function_literal(parameters, body, Some(name), env),
+                    _ => self.eval_expression(value, env)?,
+                };
+                bind_target(target, &value, env)?;
+                Ok(Object::Null)
+            }
+            Statement::Return { return_value } => {
+                let value = self.eval_expression_in(return_value, env, self.tail_calls)?;
+                Err(Unwind::Return(value))
+            }
+            Statement::While { condition, body } => self.eval_while_statement(condition, body, env),
+            Statement::For {
+                target,
+                iterable,
+                body,
+            } => self.eval_for_statement(target, iterable, body, env),
+            Statement::Throw { value, span } => {
+                let value = self.eval_expression(value, env)?;
+                let mut error = RuntimeError::from(Error::Thrown(value));
+                error.span = Some(*span);
+                Err(Unwind::Error(Box::new(error)))
+            }
+            Statement::Break => Err(Unwind::Break),
+            Statement::Continue => Err(Unwind::Continue),
+            // the parser only allows imports and exports at the top level, but
+            // a program can be built or rewritten by hand
+            Statement::Import { .. } => Err(Error::ImportNotAtTopLevel.into()),
+            Statement::Export(_) => Err(Error::ExportNotAtTopLevel.into()),
+            Statement::Expression(expression) => self.eval_expression_in(expression, env, tail),
+        }
+    }
+
+    fn eval_while_statement(
+        &mut self,
+        condition: &Expression,
+        body: &Block,
+        env: &Env,
+    ) -> EvalResult {
+        while self.eval_expression(condition, env)?.is_truthy() {
+            match self.eval_block(body, env) {
+                Ok(_) | Err(Unwind::Continue) => {}
+                Err(Unwind::Break) => break,
+                Err(unwind) => return Err(unwind),
+            }
+        }
+        Ok(Object::Null)
+    }
+
+    /// The iterable is evaluated once, each element is bound in the current scope
+    /// before running the body
+    fn eval_for_statement(
+        &mut self,
+        target: &Pattern,
+        iterable: &Expression,
+        body: &Block,
+        env: &Env,
+    ) -> EvalResult {
+        let elements = self.eval_expression(iterable, env)?.elements()?;
+
+        for element in elements {
+            bind_target(target, &element, env)?;
+            match self.eval_block(body, env) {
+                Ok(_) | Err(Unwind::Continue) => {}
+                Err(Unwind::Break) => break,
+                Err(unwind) => return Err(unwind),
+            }
+        }
+        Ok(Object::Null)
+    }
+
+    /// Evaluates statements in order, the value of a block is the value
+    /// of its last statement
+    pub(crate) fn eval_block(&mut self, block: &Block, env: &Env) -> EvalResult {
+        self.eval_block_in(block, env, false)
+    }
+
+    /// Evaluates the block, if tail is set its last statement is in tail position
+    pub(crate) fn eval_block_in(&mut self, block: &Block, env: &Env, tail: bool) -> EvalResult {
+        let mut result = Object::Null;
+        let last = block.statements.len().saturating_sub(1);
+        for (i, statement) in block.statements.iter().enumerate() {
+            result = self.eval_statement_in(statement, env, tail && i == last)?;
+        }
+        Ok(result)
+    }
+
+    /// Runs f with tail calls turned on or off, restoring the previous setting after
+    pub(crate) fn with_tail_calls<T>(
+        &mut self,
+        allowed: bool,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let previous = std::mem::replace(&mut self.tail_calls, allowed);
+        let result = f(self);
+        self.tail_calls = previous;
+        result
+    }
+}
+
+/// Destructures value into the current scope, nothing is bound if the
+/// value doesn't fit the pattern
+fn bind_target(target: &Pattern, value: &Object, env: &Env) -> Result<(), Error> {
+    let mut bindings = Vec::new();
+    bind_pattern(target, value, &mut bindings)?;
+    for (name, value) in bindings {
+        env.borrow_mut().set(&name, value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::evaluator::tests::eval_input;
+    use crate::evaluator::Evaluator;
+    use crate::lexer::Lexer;
+    use crate::object::Object;
+    use crate::parser::Parser;
+
+    #[test]
+    fn eval_let_statements() {
+        assert_eq!(eval_input("let a = 5; a;"), Ok(Object::Integer(5)));
+        assert_eq!(eval_input("let a = 5 * 5; a;"), Ok(Object::Integer(25)));
+        assert_eq!(
+            eval_input("let a = 5; let b = a; let c = a + b + 5; c;"),
+            Ok(Object::Integer(15))
+        );
+        assert_eq!(
+            eval_input("foobar"),
+            Err(Error::IdentifierNotFound("foobar".to_string()))
+        );
+    }
+
+    #[test]
+    fn eval_destructuring_let_statements() {
+        assert_eq!(
+            eval_input("let [a, b, ...rest] = [1, 2, 3, 4]; [a, b, rest]")
+                .map(|value| value.to_string()),
+            Ok("[1, 2, [3, 4]]".to_string())
+        );
+        assert_eq!(
+            eval_input("let [a, ...rest] = [1]; rest").map(|value| value.to_string()),
+            Ok("[]".to_string())
+        );
+        assert_eq!(
+            eval_input(r#"let {name, age} = {"name": "ann", "age": 30, "x": 1}; [name, age]"#)
+                .map(|value| value.to_string()),
+            Ok(r#"["ann", 30]"#.to_string())
+        );
+        assert_eq!(
+            eval_input(r#"let [_, {id}] = [0, {"id": 7}]; id"#),
+            Ok(Object::Integer(7))
+        );
+        assert_eq!(
+            eval_input(r#"let total = 0; for ({n} in [{"n": 1}, {"n": 2}]) { total += n; } total"#),
+            Ok(Object::Integer(3))
+        );
+    }
+
+    #[test]
+    fn eval_destructuring_let_errors() {
+        assert_eq!(
+            eval_input(r#"let {name, age} = {"name": "ann"};"#),
+            Err(Error::KeyNotFound("age".to_string()))
+        );
+        assert_eq!(
+            eval_input("let [a, b] = [1, 2, 3];"),
+            Err(Error::LengthMismatch {
+                expected: 2,
+                got: 3
+            })
+        );
+        assert_eq!(
+            eval_input("let [a, b, ...rest] = [1];"),
+            Err(Error::NotEnoughElements {
+                expected: 2,
+                got: 1
+            })
+        );
+        assert_eq!(
+            eval_input("let {a} = [1];"),
+            Err(Error::CannotDestructure {
+                pattern: "{a}".to_string(),
+                value: "ARRAY".to_string()
+            })
+        );
+        // nothing is bound when destructuring fails, even the names before
+        // the part that didn't match
+        let mut evaluator = Evaluator::new();
+        let mut eval = |input: &str| {
+            let program = Parser::new(Lexer::new(input.chars()))
+                .parse_program()
+                .unwrap();
+            evaluator
+                .eval_program(&program)
+                .map_err(|error| error.error)
+        };
+        assert_eq!(eval("let a = 0;"), Ok(Object::Null));
+        assert_eq!(
+            eval("let [a, {b}] = [1, 2];"),
+            Err(Error::CannotDestructure {
+                pattern: "{b}".to_string(),
+                value: "INTEGER".to_string()
+            })
+        );
+        assert_eq!(eval("a"), Ok(Object::Integer(0)));
+        assert_eq!(eval("b"), Err(Error::IdentifierNotFound("b".to_string())));
+    }
+
+    #[test]
+    fn eval_return_statements() {
+        assert_eq!(eval_input("return 10;"), Ok(Object::Integer(10)));
+        assert_eq!(eval_input("return 2 * 5; 9;"), Ok(Object::Integer(10)));
+        assert_eq!(eval_input("9; return 2 * 5; 9;"), Ok(Object::Integer(10)));
+        assert_eq!(
+            eval_input("if (10 > 1) { if (10 > 1) { return 10; } return 1; }"),
+            Ok(Object::Integer(10))
+        );
+    }
+
+    #[test]
+    fn eval_for_statements() {
+        assert_eq!(
+            eval_input("let total = 0; for (x in [1, 2, 3, 4]) { let total = total + x; } total"),
+            Ok(Object::Integer(10))
+        );
+        assert_eq!(
+            eval_input(r#"let s = ""; for (c in "héllo") { let s = c + s; } s"#),
+            Ok(Object::String("olléh".into()))
+        );
+        assert_eq!(
+            eval_input(
+                r#"let s = "";
+                for ([k, v] in {"a": 1, "b": 2}) { let s = s + k; if (v == 2) { break; } }
+                s"#
+            ),
+            Ok(Object::String("ab".into()))
+        );
+        assert_eq!(
+            eval_input(
+                "let total = 0; \
+                for ([a, b] in [[1, 2], [3, 4], [5, 6]]) { \
+                    if (a == 3) { continue; } \
+                    let total = total + a * b; \
+                } \
+                total"
+            ),
+            Ok(Object::Integer(32))
+        );
+        assert_eq!(
+            eval_input(r#"for (pair in {"a": 1}) { return pair; }"#).map(|v| v.to_string()),
+            Ok(r#"["a", 1]"#.to_string())
+        );
+    }
+
+    #[test]
+    fn eval_for_statement_errors() {
+        assert_eq!(
+            eval_input("for (x in 5) { x }"),
+            Err(Error::NotIterable("INTEGER".to_string()))
+        );
+        assert_eq!(
+            eval_input("for ([a, b] in [[1, 2, 3]]) { a }"),
+            Err(Error::LengthMismatch {
+                expected: 2,
+                got: 3
+            })
+        );
+        assert_eq!(
+            eval_input("for ([a, b] in [1]) { a }"),
+            Err(Error::CannotDestructure {
+                pattern: "[a, b]".to_string(),
+                value: "INTEGER".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn eval_while_statements() {
+        assert_eq!(eval_input("while (false) { 1 }"), Ok(Object::Null));
+        assert_eq!(
+            eval_input(
+                "let count = fn(n) { \
+                    let i = 0; \
+                    let total = 0; \
+                    while (true) { \
+                        if (i >= n) { break; } \
+                        let i = i + 1; \
+                        if (i % 2 == 0) { continue; } \
+                        let total = total + i; \
+                    } \
+                    total \
+                }; \
+                count(10);"
+            ),
+            Ok(Object::Integer(25))
+        );
+        assert_eq!(
+            eval_input(
+                "let find = fn() { \
+                    let i = 0; \
+                    while (true) { \
+                        let i = i + 1; \
+                        if (i == 7) { return i; } \
+                    } \
+                }; \
+                find();"
+            ),
+            Ok(Object::Integer(7))
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::token::{look_up_ident, Token, TokenType};
+use crate::token::{look_up_ident, Position, Span, Token, TokenType};
 use std::iter::Peekable;
 use std::str::Chars;
 
@@ -9,6 +9,11 @@ const NULL_CHAR: char = '\0';
 
 pub struct Lexer<'a> {
     input: Peekable<Chars<'a>>,
+    /// Position of the next char in the input
+    position: Position,
+    /// Number of open braces in each interpolation being read, innermost last.
+    /// The brace that closes an interpolation continues the string around it
+    interpolations: Vec<usize>,
 }
 
 impl<'a> Iterator for Lexer<'a> {
@@ -23,40 +28,108 @@ impl<'a> Lexer<'a> {
     pub fn new(input: Chars<'a>) -> Self {
         Self {
             input: input.peekable(),
+            position: Position::default(),
+            interpolations: Vec::new(),
         }
     }
 
     pub fn next_token(&mut self) -> Option<Token> {
         self.skip_white_space();
 
+        let start = self.position;
+        let mut token = self.read_token()?;
+        token.span = Span {
+            start,
+            end: self.position,
+        };
+        Some(token)
+    }
+
+    fn read_token(&mut self) -> Option<Token> {
         match self.peek_next_char() {
-            '=' => {
-                self.build_new_token_optional_double_char(TokenType::ASSIGN, &'=', TokenType::EQUAL)
-            }
+            '=' => self.build_new_token_optional_double_chars(
+                TokenType::ASSIGN,
+                &[('=', TokenType::EQUAL), ('>', TokenType::FATARROW)],
+            ),
             ';' => self.build_new_token(TokenType::SEMICOLON),
+            ':' => self.build_new_token(TokenType::COLON),
+            '.' => {
+                let dots = self.read_while(|c| *c == '.');
+                if dots == "..." {
+                    Lexer::build_new_token_with_literal(TokenType::ELLIPSIS, &dots)
+                } else {
+                    Lexer::build_new_token_with_literal(TokenType::ILLEGAL, &dots)
+                }
+            }
             '(' => self.build_new_token(TokenType::LEFTPAREN),
             ')' => self.build_new_token(TokenType::RIGHTPAREN),
             ',' => self.build_new_token(TokenType::COMMA),
-            '+' => self.build_new_token(TokenType::PLUS),
-            '-' => self.build_new_token(TokenType::MINUS),
+            '+' => self.build_new_token_optional_double_char(
+                TokenType::PLUS,
+                &'=',
+                TokenType::PLUSASSIGN,
+            ),
+            '-' => self.build_new_token_optional_double_char(
+                TokenType::MINUS,
+                &'=',
+                TokenType::MINUSASSIGN,
+            ),
             '!' => self.build_new_token_optional_double_char(
                 TokenType::BANG,
                 &'=',
                 TokenType::NOTEQUAL,
             ),
-            '*' => self.build_new_token(TokenType::ASTERISK),
-            '/' => self.build_new_token(TokenType::SLASH),
-            '<' => self.build_new_token(TokenType::LESSTHAN),
-            '>' => self.build_new_token(TokenType::GREATERTHAN),
-            '{' => self.build_new_token(TokenType::LEFTBRACE),
-            '}' => self.build_new_token(TokenType::RIGHTBRACE),
+            '*' => self.build_new_token_optional_double_char(
+                TokenType::ASTERISK,
+                &'=',
+                TokenType::ASTERISKASSIGN,
+            ),
+            '/' => self.build_new_token_optional_double_char(
+                TokenType::SLASH,
+                &'=',
+                TokenType::SLASHASSIGN,
+            ),
+            '%' => self.build_new_token(TokenType::PERCENT),
+            '<' => self.build_new_token_optional_double_char(
+                TokenType::LESSTHAN,
+                &'=',
+                TokenType::LESSTHANOREQUAL,
+            ),
+            '>' => self.build_new_token_optional_double_char(
+                TokenType::GREATERTHAN,
+                &'=',
+                TokenType::GREATERTHANOREQUAL,
+            ),
+            '{' => {
+                if let Some(open_braces) = self.interpolations.last_mut() {
+                    *open_braces += 1;
+                }
+                self.build_new_token(TokenType::LEFTBRACE)
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.read_next_char();
+                    self.read_string(false)
+                }
+                Some(open_braces) => {
+                    *open_braces -= 1;
+                    self.build_new_token(TokenType::RIGHTBRACE)
+                }
+                None => self.build_new_token(TokenType::RIGHTBRACE),
+            },
+            '[' => self.build_new_token(TokenType::LEFTBRACKET),
+            ']' => self.build_new_token(TokenType::RIGHTBRACKET),
+            '"' => {
+                self.read_next_char();
+                self.read_string(true)
+            }
 
             &NULL_CHAR => None,
 
             // if we don't match any above, we should check if it's a letter
             char_value => {
-                // TODO: implement special is alphabetic function
-                if char_value.is_alphabetic() {
+                if is_letter(char_value) {
                     // read an identifier and return
                     let identifier = self.read_identifier();
                     let identifier_token_type = look_up_ident(&identifier);
@@ -72,7 +145,7 @@ impl<'a> Lexer<'a> {
     }
 
     fn read_identifier(&mut self) -> String {
-        self.read_while(|c| c.is_alphabetic())
+        self.read_while(is_letter)
     }
 
     // TODO: we should be able to read non integer numbers also
@@ -80,12 +153,70 @@ impl<'a> Lexer<'a> {
         self.read_while(|c| c.is_numeric())
     }
 
+    /// Reads the rest of a double quoted string, up to the closing quote or the
+    /// start of an interpolation, the token literal holds the text with escape
+    /// sequences resolved. start is whether this is the first part of the
+    /// string, rather than the part after an interpolation. An unterminated
+    /// string becomes an ILLEGAL token
+    fn read_string(&mut self, start: bool) -> Option<Token> {
+        let mut result = String::new();
+        loop {
+            match self.read_next_char() {
+                '"' => {
+                    let variant = if start {
+                        TokenType::STRING
+                    } else {
+                        TokenType::STRINGEND
+                    };
+                    return Lexer::build_new_token_with_literal(variant, &result);
+                }
+                '$' if *self.peek_next_char() == '{' => {
+                    self.read_next_char();
+                    self.interpolations.push(0);
+                    let variant = if start {
+                        TokenType::STRINGSTART
+                    } else {
+                        TokenType::STRINGMIDDLE
+                    };
+                    return Lexer::build_new_token_with_literal(variant, &result);
+                }
+                '\\' => match self.read_next_char() {
+                    'n' => result.push('\n'),
+                    't' => result.push('\t'),
+                    '"' => result.push('"'),
+                    '\\' => result.push('\\'),
+                    '$' => result.push('$'),
+                    NULL_CHAR => break,
+                    other => {
+                        result.push('\\');
+                        result.push(other);
+                    }
+                },
+                NULL_CHAR => break,
+                other => result.push(other),
+            }
+        }
+
+        Lexer::build_new_token_with_literal(TokenType::ILLEGAL, &format!("\"{result}"))
+    }
+
     fn read_next_char_as_string(&mut self) -> String {
         self.read_next_char().to_string()
     }
 
     fn read_next_char(&mut self) -> char {
-        self.input.next().unwrap_or(NULL_CHAR)
+        match self.input.next() {
+            Some('\n') => {
+                self.position.line += 1;
+                self.position.column = 1;
+                '\n'
+            }
+            Some(char_value) => {
+                self.position.column += 1;
+                char_value
+            }
+            None => NULL_CHAR,
+        }
     }
 
     fn peek_next_char(&mut self) -> &char {
@@ -122,50 +253,88 @@ impl<'a> Lexer<'a> {
         single_match_token_type: TokenType,
         expected_next_char: &char,
         double_match_token_type: TokenType,
+    ) -> Option<Token> {
+        self.build_new_token_optional_double_chars(
+            single_match_token_type,
+            &[(*expected_next_char, double_match_token_type)],
+        )
+    }
+
+    /// Same as build_new_token_optional_double_char but the second char can be
+    /// any of several options, each producing a different token type
+    fn build_new_token_optional_double_chars(
+        &mut self,
+        single_match_token_type: TokenType,
+        double_matches: &[(char, TokenType)],
     ) -> Option<Token> {
         let mut matches = self.read_next_char_as_string();
-        if self.peek_next_char() == expected_next_char {
-            matches.push_str(&self.read_next_char_as_string());
-            Lexer::build_new_token_with_literal(double_match_token_type, &matches)
-        } else {
-            Lexer::build_new_token_with_literal(single_match_token_type, &matches)
+        let next_char = *self.peek_next_char();
+        match double_matches
+            .iter()
+            .find(|(expected_next_char, _)| *expected_next_char == next_char)
+        {
+            Some((_, double_match_token_type)) => {
+                matches.push_str(&self.read_next_char_as_string());
+                Lexer::build_new_token_with_literal(double_match_token_type.clone(), &matches)
+            }
+            None => Lexer::build_new_token_with_literal(single_match_token_type, &matches),
         }
     }
 }
 
+/// Identifiers are made up of letters and underscores
+fn is_letter(char_value: &char) -> bool {
+    char_value.is_alphabetic() || *char_value == '_'
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Spans are checked in next_token_spans, the other tests only look at
+    /// the kind and literal of each token
+    fn without_span(token: Token) -> Token {
+        Token::new(token.variant, &token.literal)
+    }
+
     #[test]
     fn next_token_simple_case() {
         let input = "=+(){},;";
         let mut lexer = Lexer::new(input.chars());
 
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::ASSIGN, "=")));
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::PLUS, "+")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::ASSIGN, "="))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::PLUS, "+"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::LEFTPAREN, "("))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::RIGHTPAREN, ")"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::LEFTBRACE, "{"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::RIGHTBRACE, "}"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::COMMA, ",")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::COMMA, ","))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::SEMICOLON, ";"))
         );
-        assert_eq!(lexer.next_token(), None);
+        assert_eq!(lexer.next_token().map(without_span), None);
     }
 
     #[test]
@@ -193,210 +362,558 @@ mod tests {
 
         let mut lexer = Lexer::new(input.chars());
 
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::LET, "let")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::LET, "let"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::IDENT, "five"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::ASSIGN, "=")));
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::INT, "5")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::ASSIGN, "="))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "5"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::SEMICOLON, ";"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::LET, "let")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::LET, "let"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::IDENT, "ten"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::ASSIGN, "=")));
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::INT, "10")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::ASSIGN, "="))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "10"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::SEMICOLON, ";"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::LET, "let")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::LET, "let"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::IDENT, "add"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::ASSIGN, "=")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::ASSIGN, "="))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::FUNCTION, "fn"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::LEFTPAREN, "("))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::IDENT, "x")));
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::COMMA, ",")));
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::IDENT, "y")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "x"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::COMMA, ","))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "y"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::RIGHTPAREN, ")"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::LEFTBRACE, "{"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::IDENT, "x")));
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::PLUS, "+")));
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::IDENT, "y")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "x"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::PLUS, "+"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "y"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::SEMICOLON, ";"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::RIGHTBRACE, "}"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::SEMICOLON, ";"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::LET, "let")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::LET, "let"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::IDENT, "result"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::ASSIGN, "=")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::ASSIGN, "="))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::IDENT, "add"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::LEFTPAREN, "("))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::IDENT, "five"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::COMMA, ",")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::COMMA, ","))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::IDENT, "ten"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::RIGHTPAREN, ")"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::SEMICOLON, ";"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::BANG, "!")));
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::MINUS, "-")));
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::SLASH, "/")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::BANG, "!"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::MINUS, "-"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::SLASH, "/"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::ASTERISK, "*"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::INT, "5")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "5"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::SEMICOLON, ";"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::INT, "5")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "5"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::LESSTHAN, "<"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::INT, "10")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "10"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::GREATERTHAN, ">"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::INT, "5")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "5"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::SEMICOLON, ";"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::IF, "if")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IF, "if"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::LEFTPAREN, "("))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::INT, "5")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "5"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::LESSTHAN, "<"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::INT, "10")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "10"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::RIGHTPAREN, ")"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::LEFTBRACE, "{"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::RETURN, "return"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::TRUE, "true"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::SEMICOLON, ";"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::RIGHTBRACE, "}"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::ELSE, "else"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::LEFTBRACE, "{"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::RETURN, "return"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::FALSE, "false"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::SEMICOLON, ";"))
         );
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::RIGHTBRACE, "}"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::INT, "10")));
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::EQUAL, "==")));
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::INT, "10")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "10"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::EQUAL, "=="))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "10"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::SEMICOLON, ";"))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::INT, "10")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "10"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::NOTEQUAL, "!="))
         );
-        assert_eq!(lexer.next_token(), Some(Token::new(TokenType::INT, "9")));
         assert_eq!(
-            lexer.next_token(),
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "9"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
             Some(Token::new(TokenType::SEMICOLON, ";"))
         );
-        assert_eq!(lexer.next_token(), None);
+        assert_eq!(lexer.next_token().map(without_span), None);
+    }
+
+    #[test]
+    fn next_token_comparison_and_modulo() {
+        let input = "a <= b >= c < d > e % f";
+        let mut lexer = Lexer::new(input.chars());
+
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "a"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::LESSTHANOREQUAL, "<="))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "b"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::GREATERTHANOREQUAL, ">="))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "c"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::LESSTHAN, "<"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "d"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::GREATERTHAN, ">"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "e"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::PERCENT, "%"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "f"))
+        );
+        assert_eq!(lexer.next_token().map(without_span), None);
+    }
+
+    #[test]
+    fn next_token_patterns() {
+        let input = "match (x) { my_value => _ } ... .";
+        let mut lexer = Lexer::new(input.chars());
+
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::MATCH, "match"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::LEFTPAREN, "("))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "x"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::RIGHTPAREN, ")"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::LEFTBRACE, "{"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "my_value"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::FATARROW, "=>"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "_"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::RIGHTBRACE, "}"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::ELLIPSIS, "..."))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::ILLEGAL, "."))
+        );
+        assert_eq!(lexer.next_token().map(without_span), None);
+    }
+
+    #[test]
+    fn next_token_compound_assignment() {
+        let input = "a += 1; b -= 2; c *= 3; d /= 4; e = +-*/";
+        let tokens = Lexer::new(input.chars())
+            .map(|token| token.variant)
+            .filter(|variant| *variant != TokenType::IDENT && *variant != TokenType::INT)
+            .collect::<Vec<TokenType>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                TokenType::PLUSASSIGN,
+                TokenType::SEMICOLON,
+                TokenType::MINUSASSIGN,
+                TokenType::SEMICOLON,
+                TokenType::ASTERISKASSIGN,
+                TokenType::SEMICOLON,
+                TokenType::SLASHASSIGN,
+                TokenType::SEMICOLON,
+                TokenType::ASSIGN,
+                TokenType::PLUS,
+                TokenType::MINUS,
+                TokenType::ASTERISK,
+                TokenType::SLASH,
+            ]
+        );
+    }
+
+    #[test]
+    fn next_token_collections_and_strings() {
+        let input = r#"for (x in ["foo bar", "a\"b\\c\n"]) { {"k": 1} }"#;
+        let mut lexer = Lexer::new(input.chars());
+
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::FOR, "for"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::LEFTPAREN, "("))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IDENT, "x"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::IN, "in"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::LEFTBRACKET, "["))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::STRING, "foo bar"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::COMMA, ","))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::STRING, "a\"b\\c\n"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::RIGHTBRACKET, "]"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::RIGHTPAREN, ")"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::LEFTBRACE, "{"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::LEFTBRACE, "{"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::STRING, "k"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::COLON, ":"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::INT, "1"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::RIGHTBRACE, "}"))
+        );
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::RIGHTBRACE, "}"))
+        );
+        assert_eq!(lexer.next_token().map(without_span), None);
+
+        let input = r#""unterminated"#;
+        let mut lexer = Lexer::new(input.chars());
+        assert_eq!(
+            lexer.next_token().map(without_span),
+            Some(Token::new(TokenType::ILLEGAL, "\"unterminated"))
+        );
+        assert_eq!(lexer.next_token().map(without_span), None);
+    }
+
+    #[test]
+    fn next_token_interpolated_strings() {
+        let input = r#""a ${b + {"c": "${d}"}["c"]} e ${f}" "\${g}""#;
+        let tokens = Lexer::new(input.chars())
+            .map(|token| (token.variant, token.literal))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenType::STRINGSTART, "a ".to_string()),
+                (TokenType::IDENT, "b".to_string()),
+                (TokenType::PLUS, "+".to_string()),
+                (TokenType::LEFTBRACE, "{".to_string()),
+                (TokenType::STRING, "c".to_string()),
+                (TokenType::COLON, ":".to_string()),
+                (TokenType::STRINGSTART, "".to_string()),
+                (TokenType::IDENT, "d".to_string()),
+                (TokenType::STRINGEND, "".to_string()),
+                (TokenType::RIGHTBRACE, "}".to_string()),
+                (TokenType::LEFTBRACKET, "[".to_string()),
+                (TokenType::STRING, "c".to_string()),
+                (TokenType::RIGHTBRACKET, "]".to_string()),
+                (TokenType::STRINGMIDDLE, " e ".to_string()),
+                (TokenType::IDENT, "f".to_string()),
+                (TokenType::STRINGEND, "".to_string()),
+                (TokenType::STRING, "${g}".to_string()),
+            ]
+        );
+
+        // the interpolation is never closed, so neither is the string
+        let tokens = Lexer::new(r#""a ${b"#.chars())
+            .map(|token| token.variant)
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, vec![TokenType::STRINGSTART, TokenType::IDENT]);
     }
 
     #[test]
     fn lexer_as_iterator() {
         let input = "=+(){},;";
-        let mut lexer = Lexer::new(input.chars());
-        let mut lexer = lexer.into_iter();
+        let mut lexer = Lexer::new(input.chars()).map(without_span);
 
         assert_eq!(lexer.next(), Some(Token::new(TokenType::ASSIGN, "=")));
         assert_eq!(lexer.next(), Some(Token::new(TokenType::PLUS, "+")));
@@ -413,8 +930,8 @@ mod tests {
     #[test]
     fn peekable_lexer() {
         let input = "=";
-        let mut lexer = Lexer::new(input.chars());
-        let mut peekable_lexer = lexer.peekable();
+        let lexer = Lexer::new(input.chars());
+        let mut peekable_lexer = lexer.map(without_span).peekable();
 
         // peek should remain the same
         assert_eq!(
@@ -442,4 +959,38 @@ mod tests {
         // next should be none also
         assert_eq!(peekable_lexer.next(), None);
     }
+
+    #[test]
+    fn next_token_spans() {
+        let input = "let x = \"héllo\";\n  x == 10";
+        let spans = Lexer::new(input.chars())
+            .map(|token| {
+                (
+                    token.literal,
+                    (token.span.start.line, token.span.start.column),
+                    (token.span.end.line, token.span.end.column),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            spans,
+            vec![
+                ("let".to_string(), (1, 1), (1, 4)),
+                ("x".to_string(), (1, 5), (1, 6)),
+                ("=".to_string(), (1, 7), (1, 8)),
+                ("héllo".to_string(), (1, 9), (1, 16)),
+                (";".to_string(), (1, 16), (1, 17)),
+                ("x".to_string(), (2, 3), (2, 4)),
+                ("==".to_string(), (2, 5), (2, 7)),
+                ("10".to_string(), (2, 8), (2, 10)),
+            ]
+        );
+
+        let input = "\"a ${b}\"";
+        let spans = Lexer::new(input.chars())
+            .map(|token| (token.span.start.column, token.span.end.column))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, vec![(1, 6), (6, 7), (7, 9)]);
+    }
 }
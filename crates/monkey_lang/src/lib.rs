@@ -1,5 +1,7 @@
-pub(crate) mod ast;
-pub(crate) mod error;
+pub mod ast;
+pub mod error;
+pub mod evaluator;
 pub mod lexer;
+pub mod object;
 pub mod parser;
 pub mod token;
@@ -0,0 +1,315 @@
+use crate::ast::{join_parameters, quote_string, Block, Parameter};
+use crate::error::Error;
+use crate::evaluator::environment::Env;
+use crate::evaluator::{EvalResult, Evaluator};
+use num_bigint::BigInt;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+
+/// Enum representing the runtime values produced by the evaluator
+/// collections are shared and copied on write, so cloning an object is cheap
+#[derive(Debug, Clone)]
+pub enum Object {
+    Integer(i64),
+    /// An integer that doesn't fit in an i64, integers that do are always
+    /// represented as Integer so equal values compare equal
+    BigInteger(Rc<BigInt>),
+    Boolean(bool),
+    String(Rc<str>),
+    Array(Rc<Vec<Object>>),
+    Hash(Rc<BTreeMap<HashKey, Object>>),
+    Null,
+    Function(Rc<Function>),
+    Builtin(Builtin),
+}
+
+impl Object {
+    /// Name of the type of the object, used in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Integer(_) | Object::BigInteger(_) => "INTEGER",
+            Object::Boolean(_) => "BOOLEAN",
+            Object::String(_) => "STRING",
+            Object::Array(_) => "ARRAY",
+            Object::Hash(_) => "HASH",
+            Object::Null => "NULL",
+            Object::Function(_) => "FUNCTION",
+            Object::Builtin(_) => "BUILTIN",
+        }
+    }
+
+    /// Null and false are falsy, every other value is truthy
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Object::Null | Object::Boolean(false))
+    }
+
+    /// What a for loop goes through: the elements of an array, the characters
+    /// of a string or the [key, value] pairs of a hash
+    pub(crate) fn elements(&self) -> Result<Vec<Object>, Error> {
+        match self {
+            Object::Array(elements) => Ok(elements.as_ref().clone()),
+            Object::String(value) => Ok(value
+                .chars()
+                .map(|char_value| Object::String(char_value.to_string().into()))
+                .collect()),
+            Object::Hash(pairs) => Ok(pairs
+                .iter()
+                .map(|(key, value)| {
+                    Object::Array(Rc::new(vec![Object::from(key.clone()), value.clone()]))
+                })
+                .collect()),
+            other => Err(Error::NotIterable(other.type_name().to_string())),
+        }
+    }
+
+    /// Converts the object to a hash key, only integers, booleans and
+    /// strings can be used as keys
+    pub(crate) fn to_hash_key(&self) -> Result<HashKey, Error> {
+        match self {
+            Object::Integer(value) => Ok(HashKey::Integer(*value)),
+            Object::BigInteger(value) => Ok(HashKey::BigInteger(value.clone())),
+            Object::Boolean(value) => Ok(HashKey::Boolean(*value)),
+            Object::String(value) => Ok(HashKey::String(value.clone())),
+            other => Err(Error::UnusableHashKey(other.type_name().to_string())),
+        }
+    }
+
+    /// The value of an integer of either size
+    pub(crate) fn to_big_integer(&self) -> Option<BigInt> {
+        match self {
+            Object::Integer(value) => Some(BigInt::from(*value)),
+            Object::BigInteger(value) => Some(value.as_ref().clone()),
+            _ => None,
+        }
+    }
+
+    /// Writes anything but a collection, strings are quoted when nested
+    fn fmt_scalar(&self, f: &mut Formatter<'_>, nested: bool) -> std::fmt::Result {
+        match self {
+            Object::Integer(value) => f.write_str(&format!("{value}")),
+            Object::BigInteger(value) => f.write_str(&format!("{value}")),
+            Object::Boolean(value) => f.write_str(&format!("{value}")),
+            Object::String(value) if nested => f.write_str(&quote_string(value)),
+            Object::String(value) => f.write_str(value),
+            Object::Array(_) | Object::Hash(_) => unreachable!("collections are written by fmt"),
+            Object::Null => f.write_str("null"),
+            Object::Function(function) => f.write_str(&function.to_string()),
+            Object::Builtin(builtin) => f.write_str(&format!("builtin {}", builtin.name)),
+        }
+    }
+
+    /// Moves the elements out of collections and closures nothing else holds
+    /// on to, so they can be dropped without recursing
+    fn take_children(&mut self, children: &mut Vec<Object>) {
+        match self {
+            Object::Array(elements) => {
+                if let Some(elements) = Rc::get_mut(elements) {
+                    children.append(elements);
+                }
+            }
+            Object::Hash(pairs) => {
+                if let Some(pairs) = Rc::get_mut(pairs) {
+                    children.extend(std::mem::take(pairs).into_values());
+                }
+            }
+            Object::Function(function) => {
+                // the collector holds weak references to captured scopes, so
+                // only strong references count here
+                if let Some(function) = Rc::get_mut(function) {
+                    if Rc::strong_count(&function.env) == 1 {
+                        if let Ok(mut env) = function.env.try_borrow_mut() {
+                            children.extend(env.take_values());
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for Object {
+    /// Dropping deeply nested values recursively would overflow the stack,
+    /// so the children are dropped one at a time from a list instead
+    fn drop(&mut self) {
+        let mut children = Vec::new();
+        self.take_children(&mut children);
+        while let Some(mut child) = children.pop() {
+            child.take_children(&mut children);
+        }
+    }
+}
+
+impl PartialEq for Object {
+    /// Compares nested values from a list instead of recursing, so deeply
+    /// nested values can't overflow the stack
+    fn eq(&self, other: &Self) -> bool {
+        let mut pending = vec![(self, other)];
+        while let Some((left, right)) = pending.pop() {
+            let equal = match (left, right) {
+                (Object::Integer(left), Object::Integer(right)) => left == right,
+                (Object::BigInteger(left), Object::BigInteger(right)) => left == right,
+                (Object::Boolean(left), Object::Boolean(right)) => left == right,
+                (Object::String(left), Object::String(right)) => left == right,
+                (Object::Array(left), Object::Array(right)) => {
+                    pending.extend(left.iter().zip(right.iter()));
+                    left.len() == right.len()
+                }
+                (Object::Hash(left), Object::Hash(right)) => {
+                    pending.extend(left.values().zip(right.values()));
+                    left.len() == right.len() && left.keys().eq(right.keys())
+                }
+                (Object::Null, Object::Null) => true,
+                (Object::Function(left), Object::Function(right)) => left == right,
+                (Object::Builtin(left), Object::Builtin(right)) => left == right,
+                _ => false,
+            };
+            if !equal {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// What is left to write of a value being displayed
+enum Pending<'a> {
+    /// A value, strings are quoted when nested in a collection
+    Value(&'a Object, bool),
+    Key(&'a HashKey),
+    Text(&'static str),
+}
+
+impl Display for Object {
+    /// Writes nested values from a list instead of recursing, so deeply
+    /// nested values can't overflow the stack
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut pending = vec![Pending::Value(self, false)];
+        while let Some(next) = pending.pop() {
+            match next {
+                Pending::Text(text) => f.write_str(text)?,
+                Pending::Key(key) => {
+                    Object::from(key.clone()).fmt_scalar(f, true)?;
+                    f.write_str(": ")?;
+                }
+                Pending::Value(Object::Array(elements), _) => {
+                    f.write_str("[")?;
+                    pending.push(Pending::Text("]"));
+                    for (index, element) in elements.iter().enumerate().rev() {
+                        pending.push(Pending::Value(element, true));
+                        if index > 0 {
+                            pending.push(Pending::Text(", "));
+                        }
+                    }
+                }
+                Pending::Value(Object::Hash(pairs), _) => {
+                    f.write_str("{")?;
+                    pending.push(Pending::Text("}"));
+                    for (index, (key, value)) in pairs.iter().enumerate().rev() {
+                        pending.push(Pending::Value(value, true));
+                        pending.push(Pending::Key(key));
+                        if index > 0 {
+                            pending.push(Pending::Text(", "));
+                        }
+                    }
+                }
+                Pending::Value(value, nested) => value.fmt_scalar(f, nested)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The subset of objects that can be used as hash keys
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HashKey {
+    Integer(i64),
+    BigInteger(Rc<BigInt>),
+    Boolean(bool),
+    String(Rc<str>),
+}
+
+impl From<HashKey> for Object {
+    fn from(key: HashKey) -> Self {
+        match key {
+            HashKey::Integer(value) => Object::Integer(value),
+            HashKey::BigInteger(value) => Object::BigInteger(value),
+            HashKey::Boolean(value) => Object::Boolean(value),
+            HashKey::String(value) => Object::String(value),
+        }
+    }
+}
+
+impl From<BigInt> for Object {
+    /// Only uses a big integer when the value doesn't fit in an i64
+    fn from(value: BigInt) -> Self {
+        match i64::try_from(&value) {
+            Ok(value) => Object::Integer(value),
+            Err(_) => Object::BigInteger(Rc::new(value)),
+        }
+    }
+}
+
+/// A function value, holds the environment it was defined in so
+/// it can close over it
+pub struct Function {
+    /// Name of the binding the function was defined with, if any
+    pub(crate) name: Option<String>,
+    pub(crate) parameters: Vec<Parameter>,
+    pub(crate) body: Block,
+    pub(crate) env: Env,
+}
+
+impl Debug for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // the environment is left out, it can refer back to this function
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .field("parameters", &self.parameters)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+impl Function {
+    /// Name used for the function in stack traces
+    pub(crate) fn name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| "<anonymous>".to_string())
+    }
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let comma_seperated_parameters = join_parameters(&self.parameters);
+        f.write_str(&format!("fn({comma_seperated_parameters}){}", self.body))
+    }
+}
+
+/// A function implemented in Rust, it gets the evaluator so it can call
+/// back into Monkey functions
+#[derive(Clone, Copy)]
+pub struct Builtin {
+    pub(crate) name: &'static str,
+    pub(crate) function: fn(&mut Evaluator, Vec<Object>) -> EvalResult,
+}
+
+impl Debug for Builtin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Builtin").field("name", &self.name).finish()
+    }
+}
+
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
@@ -1,9 +1,9 @@
-use crate::ast::Expression;
-use crate::error::Error;
+use crate::ast::{Block, Expression, MatchArm, Parameter, Pattern, Statement, StringPart};
+use crate::error::{Error, Warning};
 use crate::parser::util::Precedence;
 use crate::parser::Parser;
-use crate::token::{Token, TokenType};
-use std::cmp::Ordering;
+use crate::token::{Position, TokenType};
+use num_bigint::BigInt;
 
 impl<'a> Parser<'a> {
     /// Implementation of the pratt parsing technique
@@ -11,6 +11,14 @@ impl<'a> Parser<'a> {
         &mut self,
         left_precedence: Precedence,
     ) -> Result<Expression, Error> {
+        self.nested(|parser| parser.parse_nested_expression(left_precedence))
+    }
+
+    fn parse_nested_expression(
+        &mut self,
+        left_precedence: Precedence,
+    ) -> Result<Expression, Error> {
+        let start = self.peek_position();
         let mut left_expression = self.parse_null_definition()?;
 
         let (mut peek_token, mut token_at_end) = self.peek_token_return_end_status();
@@ -18,7 +26,7 @@ impl<'a> Parser<'a> {
         while !token_at_end
             && left_precedence < Precedence::get_precedence(&peek_token.unwrap().variant)
         {
-            left_expression = self.execute_infix(left_expression)?;
+            left_expression = self.execute_infix(left_expression, start)?;
             (peek_token, token_at_end) = self.peek_token_return_end_status();
         }
 
@@ -32,6 +40,10 @@ impl<'a> Parser<'a> {
             match peek_token.variant {
                 TokenType::IDENT => self.parse_identifier(),
                 TokenType::INT => self.parse_integer_literal(),
+                TokenType::STRING => self.parse_string_literal(),
+                TokenType::STRINGSTART => self.parse_interpolated_string(),
+                TokenType::LEFTBRACKET => self.parse_array_literal(),
+                TokenType::LEFTBRACE => self.parse_hash_literal(),
                 TokenType::BANG => self.parse_prefix_expression(),
                 TokenType::MINUS => self.parse_prefix_expression(),
                 TokenType::TRUE => self.parse_boolean_expression(),
@@ -39,6 +51,8 @@ impl<'a> Parser<'a> {
                 TokenType::LEFTPAREN => self.parse_grouped_expression(),
                 TokenType::IF => self.parse_if_expression(),
                 TokenType::FUNCTION => self.parse_function_literal_expression(),
+                TokenType::MATCH => self.parse_match_expression(),
+                TokenType::TRY => self.parse_try_expression(),
                 _ => Err(Error::UnexpectedToken(peek_token.literal.clone())),
             }
         } else {
@@ -46,18 +60,33 @@ impl<'a> Parser<'a> {
         }
     }
 
-    /// Determines what infix function to run based on the next token
-    fn execute_infix(&mut self, left_expression: Expression) -> Result<Expression, Error> {
+    /// Determines what infix function to run based on the next token,
+    /// start is where the left expression starts
+    fn execute_infix(
+        &mut self,
+        left_expression: Expression,
+        start: Position,
+    ) -> Result<Expression, Error> {
         let peek_token = self.peek_token().ok_or(Error::MissingToken)?;
         match &peek_token.variant {
-            TokenType::LEFTPAREN => self.parse_call_expression(left_expression),
-            _ => self.parse_infix_expression(left_expression),
+            TokenType::LEFTPAREN => self.parse_call_expression(left_expression, start),
+            TokenType::LEFTBRACKET => self.parse_index_expression(left_expression, start),
+            TokenType::ASSIGN
+            | TokenType::PLUSASSIGN
+            | TokenType::MINUSASSIGN
+            | TokenType::ASTERISKASSIGN
+            | TokenType::SLASHASSIGN => self.parse_assign_expression(left_expression, start),
+            _ => self.parse_infix_expression(left_expression, start),
         }
     }
 
     /// Builds an AST out of an infix expression
     /// e.g. 5 + 5
-    fn parse_infix_expression(&mut self, left_expression: Expression) -> Result<Expression, Error> {
+    fn parse_infix_expression(
+        &mut self,
+        left_expression: Expression,
+        start: Position,
+    ) -> Result<Expression, Error> {
         let operator_token = self.next_token()?;
         let operator_precedence = Precedence::get_precedence(&operator_token.variant);
         let right_expression = self.parse_expression(operator_precedence)?;
@@ -66,24 +95,118 @@ impl<'a> Parser<'a> {
             left: Box::new(left_expression),
             operator: operator_token.literal,
             right: Box::new(right_expression),
+            span: self.span_from(start),
+        })
+    }
+
+    /// Builds an AST for assignments e.g. x = 5 or a[0] += 1
+    /// assignment is right associative so a = b = 1 assigns 1 to both
+    fn parse_assign_expression(
+        &mut self,
+        left_expression: Expression,
+        start: Position,
+    ) -> Result<Expression, Error> {
+        if !left_expression.is_assignable() {
+            return Err(Error::InvalidAssignmentTarget(left_expression.to_string()));
+        }
+
+        let operator_token = self.next_token()?;
+        let value = self.parse_expression(Precedence::LOWEST)?;
+
+        Ok(Expression::Assign {
+            target: Box::new(left_expression),
+            operator: operator_token.literal,
+            value: Box::new(value),
+            span: self.span_from(start),
         })
     }
 
     /// Builds an AST out of an identifier token
     fn parse_identifier(&mut self) -> Result<Expression, Error> {
         let identifier_token = self.expect_next_token(TokenType::IDENT)?;
-        Ok(Expression::Identifier(identifier_token.literal))
+        Ok(Expression::Identifier(
+            identifier_token.literal,
+            identifier_token.span,
+        ))
     }
 
     /// Builds an AST out of an integer token
     fn parse_integer_literal(&mut self) -> Result<Expression, Error> {
         let int_token = self.expect_next_token(TokenType::INT)?;
-        // need to convert the integer value to an actual integer value
-        let int_value: i64 = int_token
+        // need to convert the integer value to an actual integer value,
+        // values that don't fit in an i64 become big integers
+        if let Ok(int_value) = int_token.literal.parse() {
+            return Ok(Expression::IntegerLiteral(int_value));
+        }
+        let int_value: BigInt = int_token
             .literal
             .parse()
             .map_err(|_| Error::InvalidIntegerValue(int_token.literal))?;
-        Ok(Expression::IntegerLiteral(int_value))
+        Ok(Expression::BigIntegerLiteral(int_value))
+    }
+
+    /// Builds an AST out of a string token
+    fn parse_string_literal(&mut self) -> Result<Expression, Error> {
+        let string_token = self.expect_next_token(TokenType::STRING)?;
+        Ok(Expression::StringLiteral(string_token.literal))
+    }
+
+    /// Builds an AST for strings with embedded expressions e.g. "a ${b} c",
+    /// the lexer splits them into the text around each expression
+    fn parse_interpolated_string(&mut self) -> Result<Expression, Error> {
+        let start_token = self.expect_next_token(TokenType::STRINGSTART)?;
+        let mut parts = Vec::new();
+        let mut text = start_token.literal;
+
+        loop {
+            if !text.is_empty() {
+                parts.push(StringPart::Text(text));
+            }
+            let expression = self.parse_expression(Precedence::LOWEST)?;
+            parts.push(StringPart::Expression(expression));
+
+            let token = self.next_token()?;
+            text = token.literal;
+            match token.variant {
+                TokenType::STRINGMIDDLE => {}
+                TokenType::STRINGEND => break,
+                _ => return Err(Error::UnexpectedToken(text)),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(StringPart::Text(text));
+        }
+
+        Ok(Expression::InterpolatedString(parts))
+    }
+
+    /// Builds an AST for array literals e.g. [1, 2 * 2, 3]
+    fn parse_array_literal(&mut self) -> Result<Expression, Error> {
+        self.expect_next_token(TokenType::LEFTBRACKET)?;
+
+        let mut elements = Vec::new();
+        while self.expect_next_token(TokenType::RIGHTBRACKET).is_err() {
+            elements.push(self.parse_expression(Precedence::LOWEST)?);
+            self.optional_expect_next_token(TokenType::COMMA);
+        }
+
+        Ok(Expression::ArrayLiteral(elements))
+    }
+
+    /// Builds an AST for hash literals e.g. {"one": 1, "two": 1 + 1}
+    fn parse_hash_literal(&mut self) -> Result<Expression, Error> {
+        self.expect_next_token(TokenType::LEFTBRACE)?;
+
+        let mut pairs = Vec::new();
+        while self.expect_next_token(TokenType::RIGHTBRACE).is_err() {
+            let key = self.parse_expression(Precedence::LOWEST)?;
+            self.expect_next_token(TokenType::COLON)?;
+            let value = self.parse_expression(Precedence::LOWEST)?;
+            pairs.push((key, value));
+            self.optional_expect_next_token(TokenType::COMMA);
+        }
+
+        Ok(Expression::HashLiteral(pairs))
     }
 
     /// Builds an AST out of a prefix expression
@@ -94,6 +217,7 @@ impl<'a> Parser<'a> {
         Ok(Expression::Prefix {
             operator: prefix_token.literal,
             right: Box::new(right_expression),
+            span: self.span_from(prefix_token.span.start),
         })
     }
 
@@ -127,53 +251,195 @@ impl<'a> Parser<'a> {
         Ok(grouped_expression)
     }
 
-    /// Builds an AST for If statements, with an optional else block
+    /// Builds an AST for try expressions
+    /// e.g. try { risky() } catch (e) { e["message"] }
+    fn parse_try_expression(&mut self) -> Result<Expression, Error> {
+        self.expect_next_token(TokenType::TRY)?;
+        let body = self.parse_block()?;
+
+        self.expect_next_token(TokenType::CATCH)?;
+        self.expect_next_token(TokenType::LEFTPAREN)?;
+        let name = self.expect_next_token(TokenType::IDENT)?.literal;
+        self.expect_next_token(TokenType::RIGHTPAREN)?;
+        let catch = self.parse_block()?;
+
+        Ok(Expression::Try { body, name, catch })
+    }
+
+    /// Builds an AST for If statements, with optional else if branches and
+    /// an optional else block
     fn parse_if_expression(&mut self) -> Result<Expression, Error> {
         self.expect_next_token(TokenType::IF)?;
 
         let condition = Box::new(self.parse_expression(Precedence::LOWEST)?);
         let consequence = self.parse_block()?;
-        let alternative = if self.expect_next_token(TokenType::ELSE).is_ok() {
-            Some(self.parse_block()?)
-        } else {
-            None
-        };
+
+        let mut else_ifs = Vec::new();
+        let mut alternative = None;
+        while self.expect_next_token(TokenType::ELSE).is_ok() {
+            if self.expect_next_token(TokenType::IF).is_ok() {
+                let condition = self.parse_expression(Precedence::LOWEST)?;
+                else_ifs.push((condition, self.parse_block()?));
+            } else {
+                alternative = Some(self.parse_block()?);
+                break;
+            }
+        }
 
         Ok(Expression::If {
             condition,
             consequence,
+            else_ifs,
             alternative,
         })
     }
 
+    /// Builds an AST for match expressions of the form
+    /// match (<expression>) { <pattern> => <expression or block>, ... }
+    fn parse_match_expression(&mut self) -> Result<Expression, Error> {
+        let start = self.peek_position();
+        self.expect_next_token(TokenType::MATCH)?;
+
+        let value = Box::new(self.parse_expression(Precedence::LOWEST)?);
+        self.expect_next_token(TokenType::LEFTBRACE)?;
+
+        let mut arms = Vec::new();
+        while self.expect_next_token(TokenType::RIGHTBRACE).is_err() {
+            let pattern = self.parse_pattern()?;
+            self.expect_next_token(TokenType::FATARROW)?;
+
+            // a brace here starts a block rather than a hash literal
+            let body =
+                if self.peek_token().map(|token| &token.variant) == Some(&TokenType::LEFTBRACE) {
+                    self.parse_block()?
+                } else {
+                    Block {
+                        statements: vec![Statement::Expression(
+                            self.parse_expression(Precedence::LOWEST)?,
+                        )],
+                    }
+                };
+            arms.push(MatchArm { pattern, body });
+
+            self.optional_expect_next_token(TokenType::COMMA);
+        }
+
+        self.check_boolean_exhaustiveness(&arms);
+
+        Ok(Expression::Match {
+            value,
+            arms,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Matches with boolean arms and no catch all arm are likely missing a case
+    fn check_boolean_exhaustiveness(&mut self, arms: &[MatchArm]) {
+        let has_catch_all = arms
+            .iter()
+            .any(|arm| matches!(arm.pattern, Pattern::Wildcard | Pattern::Identifier(_)));
+        let covered = arms
+            .iter()
+            .filter_map(|arm| match arm.pattern {
+                Pattern::Boolean(value) => Some(value),
+                _ => None,
+            })
+            .collect::<Vec<bool>>();
+
+        if has_catch_all || covered.is_empty() {
+            return;
+        }
+
+        for value in [true, false] {
+            if !covered.contains(&value) {
+                self.warnings
+                    .push(Warning::NonExhaustiveMatch(value.to_string()));
+            }
+        }
+    }
+
     /// Builds an AST for a function literaal expressoin
+    /// e.g. fn(x, y = 10, ...rest) { x }
     fn parse_function_literal_expression(&mut self) -> Result<Expression, Error> {
         self.expect_next_token(TokenType::FUNCTION)?;
         self.expect_next_token(TokenType::LEFTPAREN)?;
 
         let mut parameters = Vec::new();
         while self.expect_next_token(TokenType::RIGHTPAREN).is_err() {
+            if self.expect_next_token(TokenType::ELLIPSIS).is_ok() {
+                let identifier_expression = self.parse_identifier()?;
+                parameters.push(Parameter::Variadic(identifier_expression.to_string()));
+                // the variadic parameter has to come last
+                self.optional_expect_next_token(TokenType::COMMA);
+                self.expect_next_token(TokenType::RIGHTPAREN)?;
+                break;
+            }
+
             let identifier_expression = self.parse_identifier()?;
-            parameters.push(identifier_expression.to_string());
+            if self.expect_next_token(TokenType::ASSIGN).is_ok() {
+                let default = self.parse_expression(Precedence::LOWEST)?;
+                parameters.push(Parameter::Default(
+                    identifier_expression.to_string(),
+                    default,
+                ));
+            } else {
+                parameters.push(Parameter::Required(identifier_expression.to_string()));
+            }
 
             // TODO: possibility of not enforcing commas here??
             self.optional_expect_next_token(TokenType::COMMA);
         }
+        for (index, parameter) in parameters.iter().enumerate() {
+            if parameters[..index]
+                .iter()
+                .any(|earlier| earlier.name() == parameter.name())
+            {
+                return Err(Error::DuplicateParameter(parameter.name().to_string()));
+            }
+        }
 
-        let body = self.parse_block()?;
+        // loops outside the function can't be broken out of from inside it
+        let enclosing_loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let body = self.parse_block();
+        self.loop_depth = enclosing_loop_depth;
 
-        Ok(Expression::FunctionLiteral { parameters, body })
+        Ok(Expression::FunctionLiteral {
+            parameters,
+            body: body?,
+        })
     }
 
-    /// Builds an ast for call expressions e.g add(a, b)
-    fn parse_call_expression(&mut self, left_expression: Expression) -> Result<Expression, Error> {
+    /// Builds an ast for call expressions e.g add(a, b) or add(a, b: 2)
+    fn parse_call_expression(
+        &mut self,
+        left_expression: Expression,
+        start: Position,
+    ) -> Result<Expression, Error> {
         self.expect_next_token(TokenType::LEFTPAREN)?;
         let mut arguments = Vec::new();
+        let mut named_arguments = Vec::new();
 
         while self.expect_next_token(TokenType::RIGHTPAREN).is_err() {
             let argument_expression = self.parse_expression(Precedence::default());
             let argument_expression = argument_expression?;
-            arguments.push(Box::new(argument_expression));
+
+            // an identifier followed by a colon names the parameter it's for
+            match argument_expression {
+                Expression::Identifier(name, _)
+                    if self.expect_next_token(TokenType::COLON).is_ok() =>
+                {
+                    let value = self.parse_expression(Precedence::default())?;
+                    named_arguments.push((name, value));
+                }
+                argument_expression if named_arguments.is_empty() => {
+                    arguments.push(Box::new(argument_expression));
+                }
+                argument_expression => {
+                    return Err(Error::PositionalAfterNamedArgument(
+                        argument_expression.to_string(),
+                    ))
+                }
+            }
 
             // TODO: possibility of not enforcing commas here??
             self.optional_expect_next_token(TokenType::COMMA);
@@ -182,16 +448,37 @@ impl<'a> Parser<'a> {
         Ok(Expression::FunctionCall {
             function: Box::new(left_expression),
             arguments,
+            named_arguments,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Builds an ast for index expressions e.g. a[1 + 1]
+    fn parse_index_expression(
+        &mut self,
+        left_expression: Expression,
+        start: Position,
+    ) -> Result<Expression, Error> {
+        self.expect_next_token(TokenType::LEFTBRACKET)?;
+        let index = self.parse_expression(Precedence::LOWEST)?;
+        self.expect_next_token(TokenType::RIGHTBRACKET)?;
+
+        Ok(Expression::Index {
+            left: Box::new(left_expression),
+            index: Box::new(index),
+            span: self.span_from(start),
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ast::{Block, Expression, Statement};
+    use crate::ast::{Block, Expression, MatchArm, Parameter, Pattern, Statement, StringPart};
+    use crate::error::{Error, Warning};
     use crate::lexer::Lexer;
     use crate::parser::util::Precedence;
     use crate::parser::Parser;
+    use crate::token::Span;
 
     #[test]
     fn parse_identifier_expression() {
@@ -199,9 +486,15 @@ mod tests {
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
 
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
 
-        assert_eq!(expression, Expression::Identifier("foobar".to_string()));
+        assert_eq!(
+            expression,
+            Expression::Identifier("foobar".to_string(), Span::default())
+        );
     }
 
     #[test]
@@ -210,9 +503,162 @@ mod tests {
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
 
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
 
         assert_eq!(expression, Expression::IntegerLiteral(5));
+
+        // too big for an i64
+        let input = "9223372036854775808;";
+        let mut parser = Parser::new(Lexer::new(input.chars()));
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
+        assert_eq!(
+            expression,
+            Expression::BigIntegerLiteral("9223372036854775808".parse().unwrap())
+        );
+        assert_eq!(expression.to_string(), "9223372036854775808");
+    }
+
+    #[test]
+    fn parse_string_expression() {
+        let input = r#""hello world";"#;
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
+
+        assert_eq!(
+            expression,
+            Expression::StringLiteral("hello world".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_interpolated_string_expression() {
+        let input = r#""hello ${name}, you have ${len(items) + 1} items""#;
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
+
+        assert_eq!(
+            expression,
+            Expression::InterpolatedString(vec![
+                StringPart::Text("hello ".to_string()),
+                StringPart::Expression(Expression::Identifier("name".to_string(), Span::default())),
+                StringPart::Text(", you have ".to_string()),
+                StringPart::Expression(Expression::Infix {
+                    left: Box::new(Expression::FunctionCall {
+                        function: Box::new(Expression::Identifier(
+                            "len".to_string(),
+                            Span::default()
+                        )),
+                        arguments: vec![Box::new(Expression::Identifier(
+                            "items".to_string(),
+                            Span::default()
+                        ))],
+                        named_arguments: vec![],
+                        span: Span::default(),
+                    }),
+                    operator: "+".to_string(),
+                    right: Box::new(Expression::IntegerLiteral(1)),
+                    span: Span::default(),
+                }),
+                StringPart::Text(" items".to_string()),
+            ])
+        );
+        assert_eq!(
+            expression.to_string(),
+            r#""hello ${name}, you have ${(len(items) + 1)} items""#
+        );
+
+        assert_eq!(
+            parse_expression_input(r#""${"\${a}"}${b}\n""#),
+            r#""${"\${a}"}${b}\n""#
+        );
+    }
+
+    #[test]
+    fn parse_interpolated_string_errors() {
+        for (input, expected) in [
+            (r#""${}""#, Error::UnexpectedToken("".to_string())),
+            (r#""${a b}""#, Error::UnexpectedToken("b".to_string())),
+            (r#""${a"#, Error::MissingToken),
+        ] {
+            let lexer = Lexer::new(input.chars());
+            let mut parser = Parser::new(lexer);
+            assert_eq!(
+                parser.parse_expression(Precedence::default()),
+                Err(expected),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_array_and_hash_literals() {
+        let input = "[1, 2 * 2, x]";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
+
+        assert_eq!(
+            expression,
+            Expression::ArrayLiteral(vec![
+                Expression::IntegerLiteral(1),
+                Expression::Infix {
+                    left: Box::new(Expression::IntegerLiteral(2)),
+                    operator: "*".to_string(),
+                    right: Box::new(Expression::IntegerLiteral(2)),
+                    span: Span::default(),
+                },
+                Expression::Identifier("x".to_string(), Span::default()),
+            ])
+        );
+
+        let input = r#"{"one": 1, true: 2 + 3}"#;
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
+
+        assert_eq!(
+            expression,
+            Expression::HashLiteral(vec![
+                (
+                    Expression::StringLiteral("one".to_string()),
+                    Expression::IntegerLiteral(1)
+                ),
+                (
+                    Expression::Boolean(true),
+                    Expression::Infix {
+                        left: Box::new(Expression::IntegerLiteral(2)),
+                        operator: "+".to_string(),
+                        right: Box::new(Expression::IntegerLiteral(3)),
+                        span: Span::default(),
+                    }
+                ),
+            ])
+        );
+
+        assert_eq!(parse_expression_input("[]"), "[]");
+        assert_eq!(parse_expression_input("{}"), "{}");
     }
 
     #[test]
@@ -220,13 +666,19 @@ mod tests {
         let input = "true";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
         assert_eq!(expression, Expression::Boolean(true));
 
         let input = "false";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
         assert_eq!(expression, Expression::Boolean(false));
     }
 
@@ -235,46 +687,205 @@ mod tests {
         let input = "if (x < y) { x }  else { y }";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
 
         assert_eq!(
             expression,
             Expression::If {
                 condition: Box::new(Expression::Infix {
-                    left: Box::new(Expression::Identifier("x".to_string())),
+                    left: Box::new(Expression::Identifier("x".to_string(), Span::default())),
                     operator: "<".to_string(),
-                    right: Box::new(Expression::Identifier("y".to_string()))
+                    right: Box::new(Expression::Identifier("y".to_string(), Span::default())),
+                    span: Span::default(),
                 }),
                 consequence: Block {
                     statements: vec![Statement::Expression(Expression::Identifier(
-                        "x".to_string()
+                        "x".to_string(),
+                        Span::default()
                     ))]
                 },
+                else_ifs: Vec::new(),
                 alternative: Some(Block {
                     statements: vec![Statement::Expression(Expression::Identifier(
-                        "y".to_string()
+                        "y".to_string(),
+                        Span::default()
                     ))]
                 })
             }
         )
     }
 
+    #[test]
+    fn parse_else_if_expression() {
+        let input = "if (a) { 1 } else if (b) { 2 } else if (c) { 3 } else { 4 }";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
+
+        assert_eq!(
+            expression,
+            Expression::If {
+                condition: Box::new(Expression::Identifier("a".to_string(), Span::default())),
+                consequence: Block {
+                    statements: vec![Statement::Expression(Expression::IntegerLiteral(1))]
+                },
+                else_ifs: vec![
+                    (
+                        Expression::Identifier("b".to_string(), Span::default()),
+                        Block {
+                            statements: vec![Statement::Expression(Expression::IntegerLiteral(2))]
+                        }
+                    ),
+                    (
+                        Expression::Identifier("c".to_string(), Span::default()),
+                        Block {
+                            statements: vec![Statement::Expression(Expression::IntegerLiteral(3))]
+                        }
+                    ),
+                ],
+                alternative: Some(Block {
+                    statements: vec![Statement::Expression(Expression::IntegerLiteral(4))]
+                })
+            }
+        );
+        assert_eq!(
+            expression.to_string(),
+            "if(a){1;} else if(b){2;} else if(c){3;} else{4;}"
+        );
+
+        // the printed form parses back to the same tree
+        let printed = expression.to_string();
+        let lexer = Lexer::new(printed.chars());
+        let mut parser = Parser::new(lexer);
+        assert_eq!(
+            parser
+                .parse_expression(Precedence::default())
+                .unwrap()
+                .without_spans(),
+            expression
+        );
+
+        assert_eq!(
+            parse_expression_input("if (a) { 1 } else if (b) { 2 }"),
+            "if(a){1;} else if(b){2;}"
+        );
+    }
+
+    #[test]
+    fn parse_match_expression() {
+        let input = "match (x) { 1 => a, [b, _] => { b }, _ => {} }";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
+
+        assert_eq!(
+            expression,
+            Expression::Match {
+                value: Box::new(Expression::Identifier("x".to_string(), Span::default())),
+                arms: vec![
+                    MatchArm {
+                        pattern: Pattern::Integer(1),
+                        body: Block {
+                            statements: vec![Statement::Expression(Expression::Identifier(
+                                "a".to_string(),
+                                Span::default()
+                            ))]
+                        }
+                    },
+                    MatchArm {
+                        pattern: Pattern::Array(vec![
+                            Pattern::Identifier("b".to_string()),
+                            Pattern::Wildcard
+                        ]),
+                        body: Block {
+                            statements: vec![Statement::Expression(Expression::Identifier(
+                                "b".to_string(),
+                                Span::default()
+                            ))]
+                        }
+                    },
+                    MatchArm {
+                        pattern: Pattern::Wildcard,
+                        body: Block {
+                            statements: Vec::new()
+                        }
+                    },
+                ],
+                span: Span::default(),
+            }
+        );
+        assert_eq!(
+            expression.to_string(),
+            "match(x){1 => {a;}, [b, _] => {b;}, _ => {}}"
+        );
+        assert!(parser.warnings().is_empty());
+    }
+
+    #[test]
+    fn parse_match_boolean_exhaustiveness() {
+        let input = "match (x) { true => 1 }";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
+        assert_eq!(
+            parser.warnings(),
+            &[Warning::NonExhaustiveMatch("false".to_string())]
+        );
+
+        let input = "match (x) { true => 1, false => 2 }";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
+        assert!(parser.warnings().is_empty());
+
+        let input = "match (x) { false => 1, other => 2 }";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
+        assert!(parser.warnings().is_empty());
+    }
+
     #[test]
     fn parse_function_literal() {
         let input = "fn(x, y) {x + y;}";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
 
         assert_eq!(
             expression,
             Expression::FunctionLiteral {
-                parameters: vec!["x".to_string(), "y".to_string()],
+                parameters: vec![
+                    Parameter::Required("x".to_string()),
+                    Parameter::Required("y".to_string())
+                ],
                 body: Block {
                     statements: vec![Statement::Expression(Expression::Infix {
-                        left: Box::new(Expression::Identifier("x".to_string())),
+                        left: Box::new(Expression::Identifier("x".to_string(), Span::default())),
                         operator: "+".to_string(),
-                        right: Box::new(Expression::Identifier("y".to_string()))
+                        right: Box::new(Expression::Identifier("y".to_string(), Span::default())),
+                        span: Span::default(),
                     })]
                 }
             }
@@ -286,7 +897,10 @@ mod tests {
         }";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
 
         assert_eq!(
             expression,
@@ -295,15 +909,19 @@ mod tests {
                 body: Block {
                     statements: vec![
                         Statement::Let {
-                            name: "a".to_string(),
+                            target: Pattern::Identifier("a".to_string()),
                             value: Expression::IntegerLiteral(2),
                         },
                         Statement::Let {
-                            name: "b".to_string(),
+                            target: Pattern::Identifier("b".to_string()),
                             value: Expression::Infix {
-                                left: Box::new(Expression::Identifier("a".to_string())),
+                                left: Box::new(Expression::Identifier(
+                                    "a".to_string(),
+                                    Span::default()
+                                )),
                                 operator: "+".to_string(),
-                                right: Box::new(Expression::IntegerLiteral(1))
+                                right: Box::new(Expression::IntegerLiteral(1)),
+                                span: Span::default(),
                             }
                         }
                     ]
@@ -314,7 +932,10 @@ mod tests {
         let input = "fn() {}";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
 
         assert_eq!(
             expression,
@@ -329,12 +950,15 @@ mod tests {
         let input = "fn(x) {}";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
 
         assert_eq!(
             expression,
             Expression::FunctionLiteral {
-                parameters: vec!["x".to_string()],
+                parameters: vec![Parameter::Required("x".to_string())],
                 body: Block {
                     statements: Vec::new()
                 }
@@ -344,12 +968,19 @@ mod tests {
         let input = "fn(x, y, z) {}";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
 
         assert_eq!(
             expression,
             Expression::FunctionLiteral {
-                parameters: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+                parameters: vec![
+                    Parameter::Required("x".to_string()),
+                    Parameter::Required("y".to_string()),
+                    Parameter::Required("z".to_string())
+                ],
                 body: Block {
                     statements: Vec::new()
                 }
@@ -362,41 +993,151 @@ mod tests {
         let input = "add(1, 2 * 3, 4 + 5);";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
 
         assert_eq!(
             expression,
             Expression::FunctionCall {
-                function: Box::new(Expression::Identifier("add".to_string())),
+                function: Box::new(Expression::Identifier("add".to_string(), Span::default())),
                 arguments: vec![
                     Box::new(Expression::IntegerLiteral(1)),
                     Box::new(Expression::Infix {
                         left: Box::new(Expression::IntegerLiteral(2)),
                         operator: "*".to_string(),
                         right: Box::new(Expression::IntegerLiteral(3)),
+                        span: Span::default(),
                     }),
                     Box::new(Expression::Infix {
                         left: Box::new(Expression::IntegerLiteral(4)),
                         operator: "+".to_string(),
                         right: Box::new(Expression::IntegerLiteral(5)),
+                        span: Span::default(),
                     }),
-                ]
+                ],
+                named_arguments: Vec::new(),
+                span: Span::default(),
             }
         );
     }
 
+    #[test]
+    fn parse_default_variadic_and_named_parameters() {
+        assert_eq!(
+            parse_expression_input("fn(x, y = 10, ...rest) { x }"),
+            "fn(x, y = 10, ...rest){x;}"
+        );
+        assert_eq!(
+            parse_expression_input("fn(x, y = x * 2,) { y }"),
+            "fn(x, y = (x * 2)){y;}"
+        );
+        assert_eq!(
+            parse_expression_input("f(1, y: 2 + 3, z: [1])"),
+            "f(1, y: (2 + 3), z: [1])"
+        );
+
+        for input in ["fn(...rest, x) { x }", "fn(...) { 1 }"] {
+            let lexer = Lexer::new(input.chars());
+            let mut parser = Parser::new(lexer);
+            assert!(parser.parse_expression(Precedence::default()).is_err());
+        }
+
+        let lexer = Lexer::new("f(y: 1, 2)".chars());
+        let mut parser = Parser::new(lexer);
+        assert_eq!(
+            parser.parse_expression(Precedence::default()),
+            Err(Error::PositionalAfterNamedArgument("2".to_string()))
+        );
+
+        for input in [
+            "fn(a, a) { a }",
+            "fn(a, b = 1, a = 2) { a }",
+            "fn(a, ...a) { a }",
+        ] {
+            let lexer = Lexer::new(input.chars());
+            let mut parser = Parser::new(lexer);
+            assert_eq!(
+                parser.parse_expression(Precedence::default()),
+                Err(Error::DuplicateParameter("a".to_string())),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_assign_expressions() {
+        let input = "x = 5 + 1";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
+
+        assert_eq!(
+            expression,
+            Expression::Assign {
+                target: Box::new(Expression::Identifier("x".to_string(), Span::default())),
+                operator: "=".to_string(),
+                value: Box::new(Expression::Infix {
+                    left: Box::new(Expression::IntegerLiteral(5)),
+                    operator: "+".to_string(),
+                    right: Box::new(Expression::IntegerLiteral(1)),
+                    span: Span::default(),
+                }),
+                span: Span::default(),
+            }
+        );
+
+        assert_eq!(parse_expression_input("a = b = c"), "(a = (b = c))");
+        assert_eq!(parse_expression_input("a += b * 2"), "(a += (b * 2))");
+        assert_eq!(parse_expression_input("a -= 1"), "(a -= 1)");
+        assert_eq!(parse_expression_input("a *= 1"), "(a *= 1)");
+        assert_eq!(parse_expression_input("a /= 1"), "(a /= 1)");
+        assert_eq!(
+            parse_expression_input("a[i][j + 1] = x == y"),
+            "(((a[i])[(j + 1)]) = (x == y))"
+        );
+    }
+
+    #[test]
+    fn parse_invalid_assign_targets() {
+        for (input, target) in [
+            ("5 = x", "5"),
+            ("a + b = 1", "(a + b)"),
+            ("f() += 1", "f()"),
+            ("f()[0] = 1", "(f()[0])"),
+        ] {
+            let lexer = Lexer::new(input.chars());
+            let mut parser = Parser::new(lexer);
+            assert_eq!(
+                parser.parse_expression(Precedence::default()),
+                Err(Error::InvalidAssignmentTarget(target.to_string()))
+            );
+        }
+    }
+
     #[test]
     fn parse_prefix_expressions() {
         let input = "!wanted;";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
 
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
         assert_eq!(
             expression,
             Expression::Prefix {
                 operator: "!".to_string(),
-                right: Box::new(Expression::Identifier("wanted".to_string()))
+                right: Box::new(Expression::Identifier(
+                    "wanted".to_string(),
+                    Span::default()
+                )),
+                span: Span::default(),
             }
         );
 
@@ -404,12 +1145,16 @@ mod tests {
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
 
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
         assert_eq!(
             expression,
             Expression::Prefix {
                 operator: "-".to_string(),
-                right: Box::new(Expression::IntegerLiteral(15))
+                right: Box::new(Expression::IntegerLiteral(15)),
+                span: Span::default(),
             }
         );
 
@@ -417,12 +1162,16 @@ mod tests {
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
 
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
         assert_eq!(
             expression,
             Expression::Prefix {
                 operator: "!".to_string(),
-                right: Box::new(Expression::Boolean(true))
+                right: Box::new(Expression::Boolean(true)),
+                span: Span::default(),
             }
         );
 
@@ -430,20 +1179,58 @@ mod tests {
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
 
-        let expression = parser.parse_expression(Precedence::default()).unwrap();
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
         assert_eq!(
             expression,
             Expression::Prefix {
                 operator: "!".to_string(),
-                right: Box::new(Expression::Boolean(false))
+                right: Box::new(Expression::Boolean(false)),
+                span: Span::default(),
             }
         );
     }
 
-    fn parse_expression_input(input: &str) -> String {
+    #[test]
+    fn parse_expression_spans() {
+        let input = "a + f(1,\n  -b)[0]";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
         let expression = parser.parse_expression(Precedence::default()).unwrap();
+
+        let span_of = |expression: &Expression| {
+            let span = expression.span().unwrap();
+            (span.start.to_string(), span.end.to_string())
+        };
+        let Expression::Infix { left, right, .. } = &expression else {
+            panic!("expected an infix expression, got {expression}");
+        };
+        assert_eq!(span_of(&expression), ("1:1".to_string(), "2:9".to_string()));
+        assert_eq!(span_of(left), ("1:1".to_string(), "1:2".to_string()));
+        assert_eq!(span_of(right), ("1:5".to_string(), "2:9".to_string()));
+
+        let Expression::Index { left: call, .. } = right.as_ref() else {
+            panic!("expected an index expression, got {right}");
+        };
+        assert_eq!(span_of(call), ("1:5".to_string(), "2:6".to_string()));
+        let Expression::FunctionCall { arguments, .. } = call.as_ref() else {
+            panic!("expected a call expression, got {call}");
+        };
+        assert_eq!(
+            span_of(&arguments[1]),
+            ("2:3".to_string(), "2:5".to_string())
+        );
+    }
+
+    fn parse_expression_input(input: &str) -> String {
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        let expression = parser
+            .parse_expression(Precedence::default())
+            .unwrap()
+            .without_spans();
         expression.to_string()
     }
 
@@ -473,6 +1260,15 @@ mod tests {
         let input = "5 != 5;";
         assert_eq!(parse_expression_input(input), "(5 != 5)");
 
+        let input = "5 >= 5;";
+        assert_eq!(parse_expression_input(input), "(5 >= 5)");
+
+        let input = "5 <= 5;";
+        assert_eq!(parse_expression_input(input), "(5 <= 5)");
+
+        let input = "5 % 5;";
+        assert_eq!(parse_expression_input(input), "(5 % 5)");
+
         let input = "5 + 5 * 2 + 2;";
         assert_eq!(parse_expression_input(input), "((5 + (5 * 2)) + 2)");
 
@@ -515,6 +1311,12 @@ mod tests {
         let input = "5 < 4 != 3 > 4";
         assert_eq!(parse_expression_input(input), "((5 < 4) != (3 > 4))");
 
+        let input = "a + b % c * d";
+        assert_eq!(parse_expression_input(input), "(a + ((b % c) * d))");
+
+        let input = "a <= b == c >= d";
+        assert_eq!(parse_expression_input(input), "((a <= b) == (c >= d))");
+
         let input = "3 + 4 * 5 == 3 * 1 + 4 * 5";
         assert_eq!(
             parse_expression_input(input),
@@ -554,6 +1356,18 @@ mod tests {
             "add(a, b, 1, (2 * 3), (4 + 5), add(6, (7 * 8)))"
         );
 
+        let input = "a * [1, 2, 3, 4][b * c] * d";
+        assert_eq!(
+            parse_expression_input(input),
+            "((a * ([1, 2, 3, 4][(b * c)])) * d)"
+        );
+
+        let input = "add(a * b[2], b[1], 2 * [1, 2][1])";
+        assert_eq!(
+            parse_expression_input(input),
+            "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))"
+        );
+
         let input = "add(a + b + c * d / f + g)";
         assert_eq!(
             parse_expression_input(input),
@@ -1,33 +1,74 @@
 mod expression;
+mod pattern;
 mod statement;
 mod util;
 
-use crate::ast::{Expression, Program, Statement};
-use crate::error::Error;
+use crate::ast::Program;
+use crate::error::{Error, Warning};
 use crate::lexer::Lexer;
+use crate::token::{Position, TokenType};
 use std::iter::Peekable;
 
+/// Deepest nesting of expressions and patterns, the parser recurses on the
+/// Rust stack so this keeps deeply nested input from overflowing it
+const MAX_NESTING: usize = 256;
+
 pub struct Parser<'a> {
     lexer: Peekable<Lexer<'a>>,
+    /// Number of loops enclosing the current position, used to reject
+    /// break and continue outside of a loop
+    loop_depth: usize,
+    /// Number of expressions and patterns enclosing the current position
+    nesting: usize,
+    /// Where the last token taken from the lexer ended, used as the end of spans
+    previous_end: Position,
+    warnings: Vec<Warning>,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(lexer: Lexer<'a>) -> Self {
-        let mut parser = Self {
+        Self {
             lexer: lexer.peekable(),
-        };
-        parser
+            loop_depth: 0,
+            nesting: 0,
+            previous_end: Position::default(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Warnings collected while parsing so far
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
     }
 
     // TODO: might be better to keep track of a set of errors
+    /// Parses the whole input, stopping at the first syntax error
     pub fn parse_program(&mut self) -> Result<Program, Error> {
-        let mut program = Program::new();
+        let mut program = Program::new(Vec::new());
 
-        while self.lexer.peek() != None {
-            self.parse_statement()
-                .map(|statement| program.statements.push(statement))?;
+        while let Some(peek_token) = self.peek_token() {
+            // import and export are only allowed here, parse_statement rejects
+            // them inside blocks
+            let statement = match peek_token.variant {
+                TokenType::IMPORT => self.parse_import_statement(),
+                TokenType::EXPORT => self.parse_export_statement(),
+                _ => self.parse_statement(),
+            };
+            statement.map(|statement| program.statements.push(statement))?;
         }
 
         Ok(program)
     }
+
+    /// Parses something that can contain itself, failing once it's nested
+    /// too deeply instead of overflowing the stack
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.nesting == MAX_NESTING {
+            return Err(Error::NestedTooDeeply(MAX_NESTING));
+        }
+        self.nesting += 1;
+        let result = parse(self);
+        self.nesting -= 1;
+        result
+    }
 }
@@ -0,0 +1,145 @@
+use crate::ast::Pattern;
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::token::TokenType;
+use num_bigint::BigInt;
+
+impl<'a> Parser<'a> {
+    /// Parses the target of a let statement or for loop, these can't contain
+    /// literals since there is no other branch to fall back to
+    pub(crate) fn parse_binding_pattern(&mut self) -> Result<Pattern, Error> {
+        let pattern = self.parse_pattern()?;
+        if pattern.is_irrefutable() {
+            Ok(pattern)
+        } else {
+            Err(Error::RefutablePattern(pattern.to_string()))
+        }
+    }
+
+    /// Parses patterns of the form:
+    /// _, <identifier>, <literal>, [<pattern>, ..., ...<identifier>] or {<identifier>, ...}
+    pub(crate) fn parse_pattern(&mut self) -> Result<Pattern, Error> {
+        self.nested(Self::parse_nested_pattern)
+    }
+
+    fn parse_nested_pattern(&mut self) -> Result<Pattern, Error> {
+        let token = self.next_token()?;
+        match token.variant {
+            TokenType::IDENT if token.literal == "_" => Ok(Pattern::Wildcard),
+            TokenType::IDENT => Ok(Pattern::Identifier(token.literal)),
+            TokenType::INT => parse_integer(&token.literal),
+            TokenType::MINUS => {
+                let int_token = self.expect_next_token(TokenType::INT)?;
+                parse_integer(&format!("-{}", int_token.literal))
+            }
+            TokenType::STRING => Ok(Pattern::String(token.literal)),
+            TokenType::TRUE => Ok(Pattern::Boolean(true)),
+            TokenType::FALSE => Ok(Pattern::Boolean(false)),
+            TokenType::LEFTBRACKET => {
+                let mut patterns = Vec::new();
+                while self.expect_next_token(TokenType::RIGHTBRACKET).is_err() {
+                    if self.expect_next_token(TokenType::ELLIPSIS).is_ok() {
+                        let rest_token = self.expect_next_token(TokenType::IDENT)?;
+                        patterns.push(Pattern::Rest(rest_token.literal));
+                        // the rest pattern has to come last
+                        self.optional_expect_next_token(TokenType::COMMA);
+                        self.expect_next_token(TokenType::RIGHTBRACKET)?;
+                        break;
+                    }
+                    patterns.push(self.parse_pattern()?);
+                    self.optional_expect_next_token(TokenType::COMMA);
+                }
+                Ok(Pattern::Array(patterns))
+            }
+            TokenType::LEFTBRACE => {
+                let mut keys = Vec::new();
+                while self.expect_next_token(TokenType::RIGHTBRACE).is_err() {
+                    keys.push(self.expect_next_token(TokenType::IDENT)?.literal);
+                    self.optional_expect_next_token(TokenType::COMMA);
+                }
+                Ok(Pattern::Hash(keys))
+            }
+            _ => Err(Error::UnexpectedToken(token.literal)),
+        }
+    }
+}
+
+/// Integers that don't fit in an i64 become big integer patterns, the same
+/// way integer literals do
+fn parse_integer(literal: &str) -> Result<Pattern, Error> {
+    if let Ok(value) = literal.parse() {
+        return Ok(Pattern::Integer(value));
+    }
+    let value: BigInt = literal
+        .parse()
+        .map_err(|_| Error::InvalidIntegerValue(literal.to_string()))?;
+    Ok(Pattern::BigInteger(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::Pattern;
+    use crate::error::Error;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse_pattern_input(input: &str) -> Result<Pattern, Error> {
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        parser.parse_pattern()
+    }
+
+    #[test]
+    fn parse_patterns() {
+        assert_eq!(parse_pattern_input("_"), Ok(Pattern::Wildcard));
+        assert_eq!(
+            parse_pattern_input("name"),
+            Ok(Pattern::Identifier("name".to_string()))
+        );
+        assert_eq!(parse_pattern_input("42"), Ok(Pattern::Integer(42)));
+        assert_eq!(parse_pattern_input("-42"), Ok(Pattern::Integer(-42)));
+        assert_eq!(
+            parse_pattern_input("-9223372036854775808"),
+            Ok(Pattern::Integer(i64::MIN))
+        );
+        assert_eq!(
+            parse_pattern_input("18446744073709551616"),
+            Ok(Pattern::BigInteger("18446744073709551616".parse().unwrap()))
+        );
+        assert_eq!(
+            parse_pattern_input("-9223372036854775809"),
+            Ok(Pattern::BigInteger("-9223372036854775809".parse().unwrap()))
+        );
+        assert_eq!(
+            parse_pattern_input(r#""a""#),
+            Ok(Pattern::String("a".to_string()))
+        );
+        assert_eq!(parse_pattern_input("false"), Ok(Pattern::Boolean(false)));
+        assert_eq!(
+            parse_pattern_input("[a, [_, 1]]"),
+            Ok(Pattern::Array(vec![
+                Pattern::Identifier("a".to_string()),
+                Pattern::Array(vec![Pattern::Wildcard, Pattern::Integer(1)]),
+            ]))
+        );
+        assert_eq!(
+            parse_pattern_input("[head, ...tail]"),
+            Ok(Pattern::Array(vec![
+                Pattern::Identifier("head".to_string()),
+                Pattern::Rest("tail".to_string()),
+            ]))
+        );
+        assert_eq!(
+            parse_pattern_input("{name, age,}"),
+            Ok(Pattern::Hash(vec!["name".to_string(), "age".to_string()]))
+        );
+        assert_eq!(
+            parse_pattern_input("a + b"),
+            Ok(Pattern::Identifier("a".to_string()))
+        );
+        assert_eq!(
+            parse_pattern_input("(a)"),
+            Err(Error::UnexpectedToken("(".to_string()))
+        );
+    }
+}
@@ -1,9 +1,8 @@
-use crate::ast::{Block, Expression, Statement};
+use crate::ast::{Block, Statement};
 use crate::error::Error;
 use crate::parser::util::Precedence;
 use crate::parser::Parser;
 use crate::token::TokenType;
-use std::os::macos::raw::stat;
 
 impl<'a> Parser<'a> {
     pub(crate) fn parse_statement(&mut self) -> Result<Statement, Error> {
@@ -11,6 +10,13 @@ impl<'a> Parser<'a> {
             match peek_token.variant {
                 TokenType::LET => self.parse_let_statement(),
                 TokenType::RETURN => self.parse_return_statement(),
+                TokenType::THROW => self.parse_throw_statement(),
+                TokenType::WHILE => self.parse_while_statement(),
+                TokenType::FOR => self.parse_for_statement(),
+                TokenType::BREAK => self.parse_loop_control_statement(TokenType::BREAK),
+                TokenType::CONTINUE => self.parse_loop_control_statement(TokenType::CONTINUE),
+                TokenType::IMPORT => Err(Error::ImportNotAtTopLevel),
+                TokenType::EXPORT => Err(Error::ExportNotAtTopLevel),
                 _ => self.parse_expression_statement(),
             }
         } else {
@@ -19,11 +25,11 @@ impl<'a> Parser<'a> {
     }
 
     /// Parses statements of the form:
-    /// let <identifier> = <expression>;
+    /// let <pattern> = <expression>;
     fn parse_let_statement(&mut self) -> Result<Statement, Error> {
         self.expect_next_token(TokenType::LET)?;
 
-        let identifier_token = self.expect_next_token(TokenType::IDENT)?;
+        let target = self.parse_binding_pattern()?;
 
         self.expect_next_token(TokenType::ASSIGN)?;
 
@@ -32,13 +38,34 @@ impl<'a> Parser<'a> {
         self.expect_next_token(TokenType::SEMICOLON)?;
 
         Ok(Statement::Let {
-            name: identifier_token.literal,
+            target,
             value: expression,
         })
     }
 
     /// Parses statements of the form:
-    /// let <identifier> = <expression>;
+    /// import "<path>" as <identifier>;
+    pub(crate) fn parse_import_statement(&mut self) -> Result<Statement, Error> {
+        self.expect_next_token(TokenType::IMPORT)?;
+        let path = self.expect_next_token(TokenType::STRING)?.literal;
+        self.expect_next_token(TokenType::AS)?;
+        let alias = self.expect_next_token(TokenType::IDENT)?.literal;
+        self.expect_next_token(TokenType::SEMICOLON)?;
+
+        Ok(Statement::Import { path, alias })
+    }
+
+    /// Parses statements of the form:
+    /// export let <pattern> = <expression>;
+    pub(crate) fn parse_export_statement(&mut self) -> Result<Statement, Error> {
+        self.expect_next_token(TokenType::EXPORT)?;
+        let statement = self.parse_let_statement()?;
+
+        Ok(Statement::Export(Box::new(statement)))
+    }
+
+    /// Parses statements of the form:
+    /// return <expression>;
     fn parse_return_statement(&mut self) -> Result<Statement, Error> {
         self.expect_next_token(TokenType::RETURN)?;
 
@@ -51,6 +78,80 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parses statements of the form:
+    /// throw <expression>;
+    fn parse_throw_statement(&mut self) -> Result<Statement, Error> {
+        let start = self.peek_position();
+        self.expect_next_token(TokenType::THROW)?;
+
+        let value = self.parse_expression(Precedence::LOWEST)?;
+        let span = self.span_from(start);
+
+        self.expect_next_token(TokenType::SEMICOLON)?;
+
+        Ok(Statement::Throw { value, span })
+    }
+
+    /// Parses statements of the form:
+    /// while (<expression>) { <statements> }
+    fn parse_while_statement(&mut self) -> Result<Statement, Error> {
+        self.expect_next_token(TokenType::WHILE)?;
+
+        let condition = self.parse_expression(Precedence::LOWEST)?;
+
+        self.loop_depth += 1;
+        let body = self.parse_block();
+        self.loop_depth -= 1;
+
+        self.optional_expect_next_token(TokenType::SEMICOLON);
+
+        Ok(Statement::While {
+            condition,
+            body: body?,
+        })
+    }
+
+    /// Parses statements of the form:
+    /// for (<pattern> in <expression>) { <statements> }
+    fn parse_for_statement(&mut self) -> Result<Statement, Error> {
+        self.expect_next_token(TokenType::FOR)?;
+        self.expect_next_token(TokenType::LEFTPAREN)?;
+
+        let target = self.parse_binding_pattern()?;
+
+        self.expect_next_token(TokenType::IN)?;
+        let iterable = self.parse_expression(Precedence::LOWEST)?;
+        self.expect_next_token(TokenType::RIGHTPAREN)?;
+
+        self.loop_depth += 1;
+        let body = self.parse_block();
+        self.loop_depth -= 1;
+
+        self.optional_expect_next_token(TokenType::SEMICOLON);
+
+        Ok(Statement::For {
+            target,
+            iterable,
+            body: body?,
+        })
+    }
+
+    /// Parses break or continue statements, these are only valid inside a loop
+    fn parse_loop_control_statement(&mut self, token_type: TokenType) -> Result<Statement, Error> {
+        let token = self.expect_next_token(token_type)?;
+        if self.loop_depth == 0 {
+            return Err(Error::OutsideLoop(token.literal));
+        }
+
+        self.optional_expect_next_token(TokenType::SEMICOLON);
+
+        if token.variant == TokenType::BREAK {
+            Ok(Statement::Break)
+        } else {
+            Ok(Statement::Continue)
+        }
+    }
+
     /// Parses expressions, return them as an expression statement
     fn parse_expression_statement(&mut self) -> Result<Statement, Error> {
         let expression = self.parse_expression(Precedence::LOWEST)?;
@@ -80,9 +181,11 @@ impl<'a> Parser<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::ast::{Block, Expression, Statement};
+    use crate::ast::{Block, Expression, Pattern, Statement};
+    use crate::error::Error;
     use crate::lexer::Lexer;
     use crate::parser::Parser;
+    use crate::token::Span;
 
     // TODO: the tests here should parse statements not programs
     //  refactor
@@ -95,33 +198,197 @@ mod tests {
 
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let program = parser.parse_program().unwrap();
+        let program = parser.parse_program().unwrap().without_spans();
 
         assert_eq!(program.statements.len(), 3);
 
         assert_eq!(
             program.statements[0],
             Statement::Let {
-                name: "x".to_string(),
+                target: Pattern::Identifier("x".to_string()),
                 value: Expression::IntegerLiteral(5)
             }
         );
         assert_eq!(
             program.statements[1],
             Statement::Let {
-                name: "y".to_string(),
+                target: Pattern::Identifier("y".to_string()),
                 value: Expression::IntegerLiteral(10)
             }
         );
         assert_eq!(
             program.statements[2],
             Statement::Let {
-                name: "foobar".to_string(),
+                target: Pattern::Identifier("foobar".to_string()),
                 value: Expression::IntegerLiteral(838383)
             }
         );
     }
 
+    #[test]
+    fn parse_throw_and_try() {
+        let input = r#"try { throw "a" + b; } catch (e) { e }"#;
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program().unwrap().without_spans();
+
+        assert_eq!(
+            program.statements[0],
+            Statement::Expression(Expression::Try {
+                body: Block {
+                    statements: vec![Statement::Throw {
+                        value: Expression::Infix {
+                            left: Box::new(Expression::StringLiteral("a".to_string())),
+                            operator: "+".to_string(),
+                            right: Box::new(Expression::Identifier(
+                                "b".to_string(),
+                                Span::default()
+                            )),
+                            span: Span::default(),
+                        },
+                        span: Span::default(),
+                    }]
+                },
+                name: "e".to_string(),
+                catch: Block {
+                    statements: vec![Statement::Expression(Expression::Identifier(
+                        "e".to_string(),
+                        Span::default()
+                    ))]
+                },
+            })
+        );
+        assert_eq!(program.to_string(), "try{throw (\"a\" + b);} catch(e){e;};");
+
+        for input in ["try { 1 }", "try { 1 } catch { 2 }", "throw 1"] {
+            let lexer = Lexer::new(input.chars());
+            let mut parser = Parser::new(lexer);
+            assert!(parser.parse_program().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_import_and_export_statements() {
+        let input = r#"import "lib/math.mk" as math; export let [a, b] = math;"#;
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program().unwrap().without_spans();
+
+        assert_eq!(
+            program.statements[0],
+            Statement::Import {
+                path: "lib/math.mk".to_string(),
+                alias: "math".to_string()
+            }
+        );
+        assert_eq!(
+            program.statements[1],
+            Statement::Export(Box::new(Statement::Let {
+                target: Pattern::Array(vec![
+                    Pattern::Identifier("a".to_string()),
+                    Pattern::Identifier("b".to_string())
+                ]),
+                value: Expression::Identifier("math".to_string(), Span::default())
+            }))
+        );
+        assert_eq!(
+            program.to_string(),
+            "import \"lib/math.mk\" as math;\nexport let [a, b] = math;"
+        );
+
+        for input in [
+            "if (true) { import \"a.mk\" as a; }",
+            "fn() { import \"a.mk\" as a; }",
+        ] {
+            let lexer = Lexer::new(input.chars());
+            let mut parser = Parser::new(lexer);
+            assert_eq!(
+                parser.parse_program().err(),
+                Some(Error::ImportNotAtTopLevel)
+            );
+        }
+
+        for input in [
+            "if (true) { export let a = 1; }",
+            "fn() { export let a = 1; }",
+        ] {
+            let lexer = Lexer::new(input.chars());
+            let mut parser = Parser::new(lexer);
+            assert_eq!(
+                parser.parse_program().err(),
+                Some(Error::ExportNotAtTopLevel)
+            );
+        }
+
+        let lexer = Lexer::new("export 1;".chars());
+        let mut parser = Parser::new(lexer);
+        assert_eq!(
+            parser.parse_program().err(),
+            Some(Error::UnexpectedToken("1".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_destructuring_let_statements() {
+        let input = "let [a, [b, _], ...rest] = list; let {name, age} = person;";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program().unwrap().without_spans();
+
+        assert_eq!(
+            program.statements[0],
+            Statement::Let {
+                target: Pattern::Array(vec![
+                    Pattern::Identifier("a".to_string()),
+                    Pattern::Array(vec![
+                        Pattern::Identifier("b".to_string()),
+                        Pattern::Wildcard
+                    ]),
+                    Pattern::Rest("rest".to_string()),
+                ]),
+                value: Expression::Identifier("list".to_string(), Span::default())
+            }
+        );
+        assert_eq!(
+            program.statements[1],
+            Statement::Let {
+                target: Pattern::Hash(vec!["name".to_string(), "age".to_string()]),
+                value: Expression::Identifier("person".to_string(), Span::default())
+            }
+        );
+        assert_eq!(
+            program.to_string(),
+            "let [a, [b, _], ...rest] = list;\nlet {name, age} = person;"
+        );
+    }
+
+    #[test]
+    fn parse_invalid_let_targets() {
+        let input = "let [a, 1] = list;";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        assert_eq!(
+            parser.parse_program().err(),
+            Some(Error::RefutablePattern("[a, 1]".to_string()))
+        );
+
+        let input = "let [...rest, a] = list;";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        assert_eq!(
+            parser.parse_program().err(),
+            Some(Error::UnexpectedToken("a".to_string()))
+        );
+
+        let input = "let ...rest = list;";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        assert_eq!(
+            parser.parse_program().err(),
+            Some(Error::UnexpectedToken("...".to_string()))
+        );
+    }
+
     #[test]
     fn parse_return_statements() {
         let input = "return 5;\
@@ -130,7 +397,7 @@ mod tests {
 
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let program = parser.parse_program().unwrap();
+        let program = parser.parse_program().unwrap().without_spans();
 
         assert_eq!(program.statements.len(), 3);
         assert_eq!(
@@ -158,7 +425,7 @@ mod tests {
         let input = "3 + 4; -5 * 5;";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let program = parser.parse_program().unwrap();
+        let program = parser.parse_program().unwrap().without_spans();
 
         assert_eq!(program.statements.len(), 2);
         assert_eq!(
@@ -166,7 +433,8 @@ mod tests {
             Statement::Expression(Expression::Infix {
                 left: Box::new(Expression::IntegerLiteral(3)),
                 operator: "+".to_string(),
-                right: Box::new(Expression::IntegerLiteral(4))
+                right: Box::new(Expression::IntegerLiteral(4)),
+                span: Span::default(),
             })
         );
         assert_eq!(
@@ -174,33 +442,104 @@ mod tests {
             Statement::Expression(Expression::Infix {
                 left: Box::new(Expression::Prefix {
                     operator: "-".to_string(),
-                    right: Box::new(Expression::IntegerLiteral(5))
+                    right: Box::new(Expression::IntegerLiteral(5)),
+                    span: Span::default(),
                 }),
                 operator: "*".to_string(),
-                right: Box::new(Expression::IntegerLiteral(5))
+                right: Box::new(Expression::IntegerLiteral(5)),
+                span: Span::default(),
             })
         );
     }
 
+    #[test]
+    fn parse_while_statements() {
+        let input = "while (x < 10) { if (x == 5) { break; } continue }";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program().unwrap().without_spans();
+
+        assert_eq!(program.statements.len(), 1);
+        assert_eq!(
+            program.to_string(),
+            "while((x < 10)){if((x == 5)){break;};\ncontinue;}"
+        );
+
+        let input = "while (true) { let f = fn() { while (true) { break; } }; break; }";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        assert!(parser.parse_program().is_ok());
+    }
+
+    #[test]
+    fn parse_for_statements() {
+        let input = "for (x in [1, 2]) { x; } for ([k, v] in pairs) { if (k) { break; } }";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program().unwrap().without_spans();
+
+        assert_eq!(program.statements.len(), 2);
+        assert_eq!(
+            program.statements[0],
+            Statement::For {
+                target: Pattern::Identifier("x".to_string()),
+                iterable: Expression::ArrayLiteral(vec![
+                    Expression::IntegerLiteral(1),
+                    Expression::IntegerLiteral(2)
+                ]),
+                body: Block {
+                    statements: vec![Statement::Expression(Expression::Identifier(
+                        "x".to_string(),
+                        Span::default()
+                    ))]
+                }
+            }
+        );
+        assert_eq!(
+            program.statements[1].to_string(),
+            "for([k, v] in pairs){if(k){break;};}"
+        );
+    }
+
+    #[test]
+    fn parse_loop_control_outside_loop() {
+        let input = "break;";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        assert_eq!(
+            parser.parse_program().err(),
+            Some(Error::OutsideLoop("break".to_string()))
+        );
+
+        let input = "while (true) { let f = fn() { continue; }; }";
+        let lexer = Lexer::new(input.chars());
+        let mut parser = Parser::new(lexer);
+        assert_eq!(
+            parser.parse_program().err(),
+            Some(Error::OutsideLoop("continue".to_string()))
+        );
+    }
+
     #[test]
     fn parse_block() {
         let input = "{ x; 2 + 3; let a = 5; }";
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let statement = parser.parse_block().unwrap();
+        let statement = parser.parse_block().unwrap().without_spans();
 
         assert_eq!(
             statement,
             Block {
                 statements: vec![
-                    Statement::Expression(Expression::Identifier("x".to_string())),
+                    Statement::Expression(Expression::Identifier("x".to_string(), Span::default())),
                     Statement::Expression(Expression::Infix {
                         left: Box::new(Expression::IntegerLiteral(2)),
                         operator: "+".to_string(),
-                        right: Box::new(Expression::IntegerLiteral(3))
+                        right: Box::new(Expression::IntegerLiteral(3)),
+                        span: Span::default(),
                     }),
                     Statement::Let {
-                        name: "a".to_string(),
+                        target: Pattern::Identifier("a".to_string()),
                         value: Expression::IntegerLiteral(5)
                     }
                 ]
@@ -1,6 +1,6 @@
 use crate::error::Error;
 use crate::parser::Parser;
-use crate::token::{Token, TokenType};
+use crate::token::{Position, Span, Token, TokenType};
 
 impl<'a> Parser<'a> {
     pub(crate) fn peek_token(&mut self) -> Option<&Token> {
@@ -20,6 +20,7 @@ impl<'a> Parser<'a> {
 
     pub(crate) fn next_token(&mut self) -> Result<Token, Error> {
         if let Some(token) = self.lexer.next() {
+            self.previous_end = token.span.end;
             Ok(token)
         } else {
             Err(Error::MissingToken)
@@ -42,48 +43,66 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Where the next token starts, or where the input ended if there are none left
+    pub(crate) fn peek_position(&mut self) -> Position {
+        let previous_end = self.previous_end;
+        self.peek_token()
+            .map_or(previous_end, |peek_token| peek_token.span.start)
+    }
+
+    /// Span from start to the end of the last token taken
+    pub(crate) fn span_from(&self, start: Position) -> Span {
+        Span {
+            start,
+            end: self.previous_end,
+        }
+    }
+
     /// If expected token is found, it returns that and advances the lexer, else it does nothing
     pub(crate) fn optional_expect_next_token(
         &mut self,
         expected_token_variant: TokenType,
     ) -> Option<Token> {
-        if let Ok(token) = self.expect_next_token(expected_token_variant) {
-            Some(token)
-        } else {
-            None
-        }
+        self.expect_next_token(expected_token_variant).ok()
     }
 }
 
-#[derive(PartialEq, PartialOrd)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(PartialEq, PartialOrd, Default)]
 pub(crate) enum Precedence {
+    #[default]
     LOWEST,
+    ASSIGN,        // =, +=, -=, *= or /=
     EQUALS,        // ==
-    LESSORGREATER, // > or <
+    LESSORGREATER, // >, <, >= or <=
     SUM,           // +
-    PRODUCT,       // *
+    PRODUCT,       // *, / or %
     PREFIX,        // -X or !X
     CALL,          // fn(X)
-}
-
-impl Default for Precedence {
-    fn default() -> Self {
-        Self::LOWEST
-    }
+    INDEX,         // array[index]
 }
 
 impl Precedence {
     pub(crate) fn get_precedence(token_type: &TokenType) -> Self {
         match token_type {
+            TokenType::ASSIGN => Self::ASSIGN,
+            TokenType::PLUSASSIGN => Self::ASSIGN,
+            TokenType::MINUSASSIGN => Self::ASSIGN,
+            TokenType::ASTERISKASSIGN => Self::ASSIGN,
+            TokenType::SLASHASSIGN => Self::ASSIGN,
             TokenType::EQUAL => Self::EQUALS,
             TokenType::NOTEQUAL => Self::EQUALS,
             TokenType::LESSTHAN => Self::LESSORGREATER,
             TokenType::GREATERTHAN => Self::LESSORGREATER,
+            TokenType::LESSTHANOREQUAL => Self::LESSORGREATER,
+            TokenType::GREATERTHANOREQUAL => Self::LESSORGREATER,
             TokenType::PLUS => Self::SUM,
             TokenType::MINUS => Self::SUM,
             TokenType::SLASH => Self::PRODUCT,
             TokenType::ASTERISK => Self::PRODUCT,
+            TokenType::PERCENT => Self::PRODUCT,
             TokenType::LEFTPAREN => Self::CALL,
+            TokenType::LEFTBRACKET => Self::INDEX,
             _ => Self::LOWEST,
         }
     }
@@ -95,7 +114,7 @@ mod tests {
     use crate::lexer::Lexer;
     use crate::parser::util::Precedence;
     use crate::parser::Parser;
-    use crate::token::{Token, TokenType};
+    use crate::token::TokenType;
 
     #[test]
     fn expect_next_token() {
@@ -110,17 +129,22 @@ mod tests {
         );
 
         assert_eq!(
-            parser.expect_next_token(TokenType::IDENT),
-            Ok(Token::new(TokenType::IDENT, "x"))
+            parser
+                .expect_next_token(TokenType::IDENT)
+                .map(|token| (token.variant, token.literal)),
+            Ok((TokenType::IDENT, "x".to_string()))
         );
     }
 
     #[test]
     fn precedence_ordering() {
+        assert!(Precedence::INDEX > Precedence::CALL);
         assert!(Precedence::CALL > Precedence::PREFIX);
         assert!(Precedence::PREFIX > Precedence::PRODUCT);
         assert!(Precedence::PRODUCT > Precedence::SUM);
         assert!(Precedence::SUM > Precedence::LESSORGREATER);
-        assert!(Precedence::LESSORGREATER > Precedence::LOWEST);
+        assert!(Precedence::LESSORGREATER > Precedence::EQUALS);
+        assert!(Precedence::EQUALS > Precedence::ASSIGN);
+        assert!(Precedence::ASSIGN > Precedence::LOWEST);
     }
 }
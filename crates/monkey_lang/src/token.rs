@@ -1,9 +1,11 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Token {
     pub variant: TokenType,
     pub(crate) literal: String,
+    pub(crate) span: Span,
 }
 
 impl Token {
@@ -11,10 +13,43 @@ impl Token {
         Self {
             variant,
             literal: literal.to_string(),
+            span: Span::default(),
         }
     }
 }
 
+/// A place in the input, lines and columns start at 1
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self { line: 1, column: 1 }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{}:{}", self.line, self.column))
+    }
+}
+
+/// The part of the input a token or expression was read from, end is exclusive
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.start.to_string())
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum TokenType {
     ILLEGAL,
@@ -22,28 +57,47 @@ pub enum TokenType {
     // Identifiers + literals
     IDENT,
     INT,
+    STRING,
+    // Strings with embedded expressions e.g. "a ${b} c ${d} e" are split into
+    // STRINGSTART("a "), the tokens of b, STRINGMIDDLE(" c "), the tokens of d
+    // and STRINGEND(" e")
+    STRINGSTART,
+    STRINGMIDDLE,
+    STRINGEND,
 
     // Operators
     ASSIGN,
+    FATARROW,
+    PLUSASSIGN,
+    MINUSASSIGN,
+    ASTERISKASSIGN,
+    SLASHASSIGN,
     PLUS,
     MINUS,
     BANG,
     ASTERISK,
     SLASH,
+    PERCENT,
 
     LESSTHAN,
     GREATERTHAN,
+    LESSTHANOREQUAL,
+    GREATERTHANOREQUAL,
     EQUAL,
     NOTEQUAL,
 
     // Delimiters
     COMMA,
     SEMICOLON,
+    COLON,
+    ELLIPSIS,
 
     LEFTPAREN,
     RIGHTPAREN,
     LEFTBRACE,
     RIGHTBRACE,
+    LEFTBRACKET,
+    RIGHTBRACKET,
 
     // Keywords
     FUNCTION,
@@ -53,6 +107,18 @@ pub enum TokenType {
     IF,
     ELSE,
     RETURN,
+    WHILE,
+    BREAK,
+    CONTINUE,
+    FOR,
+    IN,
+    MATCH,
+    IMPORT,
+    EXPORT,
+    AS,
+    TRY,
+    CATCH,
+    THROW,
 }
 
 pub(crate) fn look_up_ident(ident: &str) -> TokenType {
@@ -64,6 +130,18 @@ pub(crate) fn look_up_ident(ident: &str) -> TokenType {
         ("if", TokenType::IF),
         ("else", TokenType::ELSE),
         ("return", TokenType::RETURN),
+        ("while", TokenType::WHILE),
+        ("break", TokenType::BREAK),
+        ("continue", TokenType::CONTINUE),
+        ("for", TokenType::FOR),
+        ("in", TokenType::IN),
+        ("match", TokenType::MATCH),
+        ("import", TokenType::IMPORT),
+        ("export", TokenType::EXPORT),
+        ("as", TokenType::AS),
+        ("try", TokenType::TRY),
+        ("catch", TokenType::CATCH),
+        ("throw", TokenType::THROW),
     ]);
 
     keyword_map.get(ident).cloned().unwrap_or(TokenType::IDENT)
@@ -0,0 +1,73 @@
+use monkey_lang::ast::visit::{walk_block_mut, VisitorMut};
+use monkey_lang::ast::{Block, Expression, MatchArm, Pattern, Program, Statement};
+use monkey_lang::error::Error;
+use monkey_lang::evaluator::Evaluator;
+use monkey_lang::lexer::Lexer;
+use monkey_lang::object::Object;
+use monkey_lang::parser::Parser;
+use monkey_lang::token::Span;
+
+fn parse(input: &str) -> Result<Program, Error> {
+    Parser::new(Lexer::new(input.chars())).parse_program()
+}
+
+/// Makes every block count how many times it ran in a global `runs`
+struct CountRuns;
+
+impl VisitorMut for CountRuns {
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        walk_block_mut(self, block);
+        block.statements_mut().insert(
+            0,
+            Statement::Expression(Expression::Assign {
+                target: Box::new(Expression::Identifier("runs".to_string(), Span::default())),
+                operator: "+=".to_string(),
+                value: Box::new(Expression::IntegerLiteral(1)),
+                span: Span::default(),
+            }),
+        );
+    }
+}
+
+#[test]
+fn rewrite_a_program_with_new_nodes() {
+    let mut program = parse("let i = 0; while (i < 3) { if (i == 1) { 0 }; i += 1; }").unwrap();
+    CountRuns.visit_program_mut(&mut program);
+    program.statements_mut().insert(
+        0,
+        Statement::Let {
+            target: Pattern::Identifier("runs".to_string()),
+            value: Expression::IntegerLiteral(0),
+        },
+    );
+    program
+        .statements_mut()
+        .push(Statement::Expression(Expression::Match {
+            value: Box::new(Expression::Identifier("runs".to_string(), Span::default())),
+            arms: vec![MatchArm::new(
+                Pattern::Identifier("n".to_string()),
+                Block::new(vec![Statement::Expression(Expression::Identifier(
+                    "n".to_string(),
+                    Span::default(),
+                ))]),
+            )],
+            span: Span::default(),
+        }));
+
+    assert_eq!(
+        Evaluator::new().eval_program(&program),
+        Ok(Object::Integer(4))
+    );
+}
+
+#[test]
+fn parse_errors_can_be_matched() {
+    assert_eq!(
+        parse("let = 1;"),
+        Err(Error::UnexpectedToken("=".to_string()))
+    );
+    assert_eq!(
+        parse("break;"),
+        Err(Error::OutsideLoop("break".to_string()))
+    );
+}
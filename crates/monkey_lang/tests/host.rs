@@ -0,0 +1,53 @@
+use monkey_lang::error::{Error, RuntimeError};
+use monkey_lang::evaluator::limits::{Limit, Limits};
+use monkey_lang::evaluator::{Evaluator, Options};
+use monkey_lang::lexer::Lexer;
+use monkey_lang::object::Object;
+use monkey_lang::parser::Parser;
+
+fn eval(evaluator: &mut Evaluator, input: &str) -> Result<Object, RuntimeError> {
+    let program = Parser::new(Lexer::new(input.chars()))
+        .parse_program()
+        .unwrap();
+    evaluator.eval_program(&program)
+}
+
+#[test]
+fn hosts_can_tell_limits_apart_from_script_errors() {
+    let mut evaluator = Evaluator::with_options(Options {
+        limits: Limits {
+            max_steps: Some(1000),
+            ..Limits::default()
+        },
+        ..Options::default()
+    });
+
+    let error = eval(&mut evaluator, "while (true) {}").unwrap_err();
+    assert!(matches!(
+        error.error(),
+        Error::LimitExceeded(Limit::Steps(1000))
+    ));
+
+    let error = eval(&mut evaluator, "let f = fn() { 1 / 0 };\nf()").unwrap_err();
+    assert_eq!(error.error(), &Error::DivisionByZero);
+    assert_eq!(
+        error.span().map(|span| span.start.to_string()),
+        Some("1:16".to_string())
+    );
+    assert_eq!(
+        error
+            .stack()
+            .iter()
+            .map(|frame| (frame.function(), frame.call_site().start.to_string()))
+            .collect::<Vec<_>>(),
+        vec![("f", "2:1".to_string())]
+    );
+}
+
+#[test]
+fn hosts_can_tell_cancellation_apart_from_script_errors() {
+    let mut evaluator = Evaluator::new();
+    evaluator.cancellation_handle().cancel();
+    let error = eval(&mut evaluator, "1 + 1").unwrap_err();
+    assert_eq!(error.error(), &Error::Cancelled);
+}
@@ -1,27 +1,70 @@
+use monkey_lang::evaluator::capabilities::Capabilities;
+use monkey_lang::evaluator::{Evaluator, Options};
 use monkey_lang::lexer::Lexer;
+use monkey_lang::object::Object;
 use monkey_lang::parser::Parser;
-use monkey_lang::token::TokenType;
 use std::io;
 use std::io::Write;
 
-// TODO: if you encounter a parse error pretty print it
 fn main() -> io::Result<()> {
     let stdin = io::stdin();
     let mut stdout = io::stdout();
     let mut input = String::new();
+    let script = std::env::args().nth(1);
+
+    // modules can be imported from the working directory and the script's directory
+    let mut modules = vec![std::env::current_dir()?];
+    if let Some(directory) = script
+        .as_ref()
+        .and_then(|path| std::path::Path::new(path).parent())
+    {
+        modules.push(directory.to_path_buf());
+    }
+    let mut evaluator = Evaluator::with_options(Options {
+        capabilities: Capabilities {
+            modules,
+            ..Capabilities::default()
+        },
+        ..Options::default()
+    });
+
+    // run a script instead of starting the repl when given a path
+    if let Some(path) = script {
+        match evaluator.eval_file(&path) {
+            Ok(Object::Null) => {}
+            Ok(value) => println!("{}", value),
+            Err(error) => {
+                println!("error: {}", error);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
 
     loop {
         write!(stdout, "→ ")?;
         stdout.flush()?;
 
-        stdin.read_line(&mut input)?;
+        if stdin.read_line(&mut input)? == 0 {
+            return Ok(());
+        }
 
         // build a lexer from this and then call parser
         let lexer = Lexer::new(input.chars());
         let mut parser = Parser::new(lexer);
-        let program = parser.parse_program().unwrap();
+        let program = parser.parse_program();
+        for warning in parser.warnings() {
+            println!("warning: {}", warning);
+        }
 
-        println!("{}", program);
+        match program {
+            Ok(program) => match evaluator.eval_program(&program) {
+                Ok(Object::Null) => {}
+                Ok(value) => println!("{}", value),
+                Err(error) => println!("error: {}", error),
+            },
+            Err(error) => println!("parse error: {}", error),
+        }
 
         input.clear();
     }